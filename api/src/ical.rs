@@ -0,0 +1,264 @@
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use model::common::onair::{Broadcast, Schedule};
+
+const PRODID: &str = "-//b38.dev//api//EN";
+const UID_DOMAIN: &str = "api.b38.dev";
+/// Most occurrences listed as RDATEs for a period RRULE cannot express.
+const MAX_RDATES: u32 = 500;
+
+/// A minimal RFC 5545 calendar.
+pub struct Calendar {
+    pub name: String,
+    pub events: Vec<Event>,
+}
+
+pub struct Event {
+    pub uid: String,
+    pub start: DateTime<Utc>,
+    pub summary: String,
+    pub url: Option<String>,
+    pub rrule: Option<String>,
+    /// Occurrences after `start` when `rrule` cannot express the period.
+    pub rdates: Vec<DateTime<Utc>>,
+}
+
+impl Event {
+    /// A recurring event following `schedule`, ending at `end` when only
+    /// that bounds it and after its episode count otherwise. Periods without
+    /// an RRULE equivalent are listed as RDATEs; `None` when such a schedule
+    /// has no end.
+    pub fn recurring(id: &str, summary: String, schedule: &Schedule) -> Option<Self> {
+        let first = schedule.first_airing()?;
+        let count = schedule.count();
+        let limit = match (schedule.end, schedule.broadcast.limit(), schedule.episodes) {
+            (Some(end), None, None) => Limit::Until(end),
+            _ => count.map_or(Limit::Never, Limit::Count),
+        };
+        let rrule = rrule(&schedule.broadcast, limit);
+        let mut rdates = Vec::new();
+        if rrule.is_none() && count != Some(1) {
+            match count {
+                Some(count) if count <= MAX_RDATES => {
+                    rdates = schedule
+                        .between(first.time, DateTime::<Utc>::MAX_UTC)
+                        .skip(1)
+                        .map(|airing| airing.time)
+                        .collect();
+                }
+                _ => {
+                    tracing::warn!(
+                        "Skipping event {id}: period of {:?} has no RRULE equivalent and {:?} occurrences",
+                        schedule.broadcast,
+                        count
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(Self {
+            uid: format!("{id}@{UID_DOMAIN}"),
+            start: first.time,
+            summary,
+            url: None,
+            rrule,
+            rdates,
+        })
+    }
+
+    pub fn url(mut self, url: Option<String>) -> Self {
+        self.url = url.filter(|u| !u.is_empty());
+        self
+    }
+}
+
+pub enum Limit {
+    Never,
+    Until(DateTime<Utc>),
+    Count(u32),
+}
+
+/// RRULE equivalent of the broadcast period, `None` for one-off broadcasts
+/// and for periods mixing months with fixed durations.
+pub fn rrule(broadcast: &Broadcast, limit: Limit) -> Option<String> {
+    let period = &broadcast.period;
+    if period.is_zero() || broadcast.limit() == Some(1) {
+        return None;
+    }
+    let months = period.calendar_months();
+    let seconds = period.fixed().num_seconds();
+    let (freq, interval) = match (months, seconds) {
+        (0, s) if s % (7 * 86400) == 0 => ("WEEKLY", s / (7 * 86400)),
+        (0, s) if s % 86400 == 0 => ("DAILY", s / 86400),
+        (0, s) if s % 3600 == 0 => ("HOURLY", s / 3600),
+        (0, s) if s % 60 == 0 => ("MINUTELY", s / 60),
+        (0, s) => ("SECONDLY", s),
+        (m, 0) if m % 12 == 0 => ("YEARLY", (m / 12) as i64),
+        (m, 0) => ("MONTHLY", m as i64),
+        _ => return None,
+    };
+    let mut rule = format!("FREQ={freq};INTERVAL={interval}");
+    match limit {
+        Limit::Until(until) => rule.push_str(&format!(";UNTIL={}", format_time(until))),
+        Limit::Count(count) => rule.push_str(&format!(";COUNT={count}")),
+        Limit::Never => {}
+    }
+    Some(rule)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets without splitting UTF-8 sequences.
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+impl Calendar {
+    pub fn render(&self) -> String {
+        let now = format_time(Utc::now());
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{PRODID}"),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!("X-WR-CALNAME:{}", escape(&self.name)),
+        ];
+        for event in &self.events {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}", event.uid));
+            lines.push(format!("DTSTAMP:{now}"));
+            lines.push(format!("DTSTART:{}", format_time(event.start)));
+            if let Some(rrule) = &event.rrule {
+                lines.push(format!("RRULE:{rrule}"));
+            }
+            if !event.rdates.is_empty() {
+                let rdates = event.rdates.iter().map(|t| format_time(*t));
+                lines.push(format!("RDATE:{}", rdates.collect::<Vec<_>>().join(",")));
+            }
+            lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+            if let Some(url) = &event.url {
+                lines.push(format!("URL:{url}"));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+        let mut out = String::new();
+        for line in lines {
+            fold(&line, &mut out);
+        }
+        out
+    }
+}
+
+impl IntoResponse for Calendar {
+    fn into_response(self) -> Response {
+        (
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            self.render(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rrule() {
+        let weekly: Broadcast = "R/2025-07-06T12:00:00.000Z/P7D".parse().unwrap();
+        let until = DateTime::parse_from_rfc3339("2025-09-28T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            Some("FREQ=WEEKLY;INTERVAL=1;UNTIL=20250928T123000Z".to_string()),
+            rrule(&weekly, Limit::Until(until))
+        );
+        let monthly: Broadcast = "R/2025-07-06T12:00:00.000Z/P1M".parse().unwrap();
+        assert_eq!(
+            Some("FREQ=MONTHLY;INTERVAL=1;COUNT=3".to_string()),
+            rrule(&monthly, Limit::Count(3))
+        );
+        let once: Broadcast = "R/2025-07-06T12:00:00.000Z/P0D".parse().unwrap();
+        assert_eq!(None, rrule(&once, Limit::Never));
+    }
+
+    fn schedule(broadcast: &str, end: Option<&str>) -> Schedule {
+        Schedule {
+            broadcast: broadcast.parse().unwrap(),
+            begin: None,
+            end: end.map(|e| DateTime::parse_from_rfc3339(e).unwrap().with_timezone(&Utc)),
+            episodes: None,
+        }
+    }
+
+    #[test]
+    fn test_recurring() {
+        // the repeat count wins over a later `end`
+        let s = schedule(
+            "R3/2025-07-06T12:00:00.000Z/P7D",
+            Some("2025-12-31T00:00:00Z"),
+        );
+        let event = Event::recurring("a", String::new(), &s).unwrap();
+        assert_eq!(
+            Some("FREQ=WEEKLY;INTERVAL=1;COUNT=3"),
+            event.rrule.as_deref()
+        );
+        // `end` alone is kept as UNTIL
+        let s = schedule(
+            "R/2025-07-06T12:00:00.000Z/P7D",
+            Some("2025-09-28T12:30:00Z"),
+        );
+        let event = Event::recurring("a", String::new(), &s).unwrap();
+        assert_eq!(
+            Some("FREQ=WEEKLY;INTERVAL=1;UNTIL=20250928T123000Z"),
+            event.rrule.as_deref()
+        );
+        // months and days mixed are listed one by one
+        let s = schedule("R3/2025-07-06T12:00:00.000Z/P1M1D", None);
+        let event = Event::recurring("b", String::new(), &s).unwrap();
+        assert_eq!(None, event.rrule);
+        assert_eq!(2, event.rdates.len());
+        let calendar = Calendar {
+            name: String::new(),
+            events: vec![event],
+        };
+        assert!(
+            calendar
+                .render()
+                .contains("RDATE:20250807T120000Z,20250908T120000Z\r\n")
+        );
+        // and dropped when they never end
+        let s = schedule("R/2025-07-06T12:00:00.000Z/P1M1D", None);
+        assert!(Event::recurring("c", String::new(), &s).is_none());
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut out = String::new();
+        fold(&format!("SUMMARY:{}", "瑠".repeat(30)), &mut out);
+        let lines = out.split("\r\n").collect::<Vec<_>>();
+        assert!(lines.iter().all(|l| l.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(escape("a,b;c\\d"), "a\\,b\\;c\\\\d");
+    }
+}
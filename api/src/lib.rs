@@ -1,4 +1,6 @@
 pub mod error;
+pub mod ical;
+pub mod query;
 #[cfg(feature = "v1")]
 pub mod v1;
 
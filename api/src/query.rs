@@ -0,0 +1,119 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use std::str::FromStr;
+
+/// Parse a timezone offset such as `+08:00`, `+0800`, `+8`, `UTC+8` or `Z`.
+/// A leading space is read as `+`, since that is what an unescaped `+`
+/// in a query string decodes to.
+pub fn parse_tz(s: &str) -> Option<FixedOffset> {
+    let s = match s.strip_prefix(' ') {
+        Some(rest) => format!("+{}", rest.trim()),
+        None => s.trim().to_string(),
+    };
+    let s = s
+        .strip_prefix("UTC")
+        .or_else(|| s.strip_prefix("GMT"))
+        .unwrap_or(&s);
+    if s.is_empty() || s.eq_ignore_ascii_case("z") {
+        return FixedOffset::east_opt(0);
+    }
+    // `FixedOffset::from_str` ignores anything after `+HH:MM` or `+HHMM`
+    if matches!(s.len(), 5 | 6)
+        && let Ok(tz) = FixedOffset::from_str(s)
+    {
+        return Some(tz);
+    }
+    let (sign, hours) = match s.split_at(1) {
+        ("+", hours) => (1, hours),
+        ("-", hours) => (-1, hours),
+        _ => (1, s),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    // real offsets range from -12 to +14
+    if !(0..=14).contains(&hours) {
+        return None;
+    }
+    FixedOffset::east_opt(sign * hours * 3600)
+}
+
+pub fn deserialize_tz<'de, D>(deserializer: D) -> Result<Option<FixedOffset>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    let Some(s) = s else {
+        return Ok(None);
+    };
+    parse_tz(&s)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid timezone: {s}")))
+}
+
+/// Optional value parsed with its `FromStr`, for enums with aliases.
+pub fn deserialize_parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+{
+    let s: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    let Some(s) = s else {
+        return Ok(None);
+    };
+    s.parse()
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("invalid value: {s}")))
+}
+
+/// Comma separated list, silently dropping entries that fail to parse.
+pub fn deserialize_list<'de, D, T, C>(deserializer: D) -> Result<C, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: FromStr,
+    C: FromIterator<T>,
+{
+    let s: String = serde::Deserialize::deserialize(deserializer)?;
+    let list = s
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    Ok(list)
+}
+
+/// RFC 3339 timestamp, or a `YYYY-MM-DD` date taken as midnight UTC.
+pub fn deserialize_time<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    let Some(s) = s else {
+        return Ok(None);
+    };
+    if let Ok(time) = DateTime::parse_from_rfc3339(&s) {
+        return Ok(Some(time.with_timezone(&Utc)));
+    }
+    NaiveDate::parse_from_str(&s, "%Y-%m-%d")
+        .map(|date| Some(date.and_time(NaiveTime::MIN).and_utc()))
+        .map_err(|_| serde::de::Error::custom(format!("invalid time: {s}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tz() {
+        let east = |hours| FixedOffset::east_opt(hours * 3600);
+        assert_eq!(east(8), parse_tz("+08:00"));
+        assert_eq!(east(8), parse_tz("+0800"));
+        assert_eq!(east(8), parse_tz(" 8"));
+        assert_eq!(east(8), parse_tz("UTC+8"));
+        assert_eq!(east(-5), parse_tz("-5"));
+        assert_eq!(east(0), parse_tz("Z"));
+        assert_eq!(east(14), parse_tz("+14"));
+        assert_eq!(None, parse_tz("+15"));
+        assert_eq!(None, parse_tz("+1000000"));
+        assert_eq!(None, parse_tz("+2147483647"));
+        assert_eq!(None, parse_tz("-2147483648"));
+    }
+}
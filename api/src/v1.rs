@@ -2,10 +2,15 @@ use crate::AppState;
 use axum::Router;
 
 pub mod onair;
+pub mod stats;
+pub mod status;
 pub mod user;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .nest("/onair", onair::routes())
         .nest("/user", user::routes())
+        .route("/users", axum::routing::get(user::list_users))
+        .nest("/stats", stats::routes())
+        .nest("/status", status::routes())
 }
@@ -1,37 +1,238 @@
 use crate::AppState;
+use crate::ical::{Calendar, Event};
 
-use axum::{Json, Router, extract::Query, routing::get};
-use model::common::onair::{BangumiItem, BangumiItemMap, SubjectId, SubjectIds};
+use axum::{
+    Json, Router,
+    extract::{Path, Query},
+    routing::get,
+};
+use chrono::{DateTime, Days, FixedOffset, Months, NaiveDate, NaiveTime, Offset, Utc};
+use model::common::onair::bangumi_data::{self, ItemType, Language, SiteType};
+use model::common::onair::{
+    BangumiItem, BangumiItemMap, ChangeKind, Diff, NextAir, Site, SiteMeta, SiteMetaMap, SubjectId,
+    SubjectIds,
+};
+use model::common::text::MatchKind;
+use model::prelude::{OnAir, OnAirChange};
+use std::collections::HashMap;
+
+/// Longest range `/calendar` expands in one request.
+const MAX_CALENDAR_DAYS: u64 = 62;
+const DEFAULT_PAGE_LIMIT: u64 = 50;
+const MAX_PAGE_LIMIT: u64 = 200;
+const DEFAULT_SEARCH_LIMIT: u64 = 20;
+const MAX_SEARCH_LIMIT: u64 = 100;
+const DEFAULT_CHANGES_LIMIT: u64 = 100;
+const MAX_CHANGES_LIMIT: u64 = 1000;
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/", get(query_by_subjects))
+    Router::new()
+        .route("/", get(query_by_subjects))
+        .route("/calendar", get(query_calendar))
+        .route("/calendar.ics", get(query_calendar_ics))
+        .route("/search", get(search))
+        .route("/sites", get(query_sites))
+        .route("/season/{year}/{quarter}", get(query_by_season))
+        .route("/changes", get(query_changes))
+        .route("/{id}/history", get(query_history))
+}
+
+#[derive(serde::Serialize)]
+pub struct Item((SubjectId, ItemView));
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemView {
+    #[serde(flatten)]
+    pub item: ItemBody,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<Upcoming>,
+    /// When the item was dropped from bangumi-data, only present on
+    /// tombstoned items requested with `include_removed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+impl From<OnAir> for ItemView {
+    fn from(model: OnAir) -> Self {
+        Self {
+            item: ItemBody {
+                item: model.data,
+                sites: None,
+            },
+            next: None,
+            removed_at: model.removed_at,
+        }
+    }
+}
+
+impl ItemView {
+    pub fn with_next(mut self, at: DateTime<Utc>) -> Self {
+        self.next = Some(Upcoming::new(&self.item.item, at));
+        self
+    }
+
+    /// Render site URLs and attach the `regions`/`type` of each site.
+    pub fn expand(mut self, site_meta: &SiteMetaMap) -> Self {
+        let sites = self.item.item.sites.iter_mut().map(|site| {
+            let meta = site_meta.get(&site.site);
+            if let Some(meta) = meta {
+                site.expand(meta);
+            }
+            SiteView::new(site.clone(), meta)
+        });
+        self.item.sites = Some(sites.collect());
+        self
+    }
+}
+
+/// An item as stored, with its sites replaced by `sites` once expanded.
+pub struct ItemBody {
+    pub item: BangumiItem,
+    pub sites: Option<Vec<SiteView>>,
+}
+
+impl serde::Serialize for ItemBody {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+        let Some(sites) = &self.sites else {
+            return self.item.serialize(serializer);
+        };
+        let mut value = serde_json::to_value(&self.item).map_err(S::Error::custom)?;
+        value["sites"] = serde_json::to_value(sites).map_err(S::Error::custom)?;
+        value.serialize(serializer)
+    }
 }
 
+/// A site with what its site meta knows about it, which bangumi-data does
+/// not repeat on every item.
 #[derive(serde::Serialize)]
-pub struct Item((SubjectId, BangumiItem));
+pub struct SiteView {
+    #[serde(flatten)]
+    pub site: bangumi_data::Site,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regions: Option<Vec<String>>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub site_type: Option<SiteType>,
+}
+
+impl SiteView {
+    fn new(site: bangumi_data::Site, meta: Option<&SiteMeta>) -> Self {
+        Self {
+            site,
+            regions: meta
+                .filter(|meta| !meta.regions.is_empty())
+                .map(|meta| meta.regions.clone()),
+            site_type: meta.and_then(|meta| meta.site_type.clone()),
+        }
+    }
+}
+
+/// Next airing of the main broadcast and of every site with a broadcast.
+#[derive(serde::Serialize)]
+pub struct Upcoming {
+    #[serde(flatten)]
+    pub main: Option<NextAir>,
+    pub sites: Vec<SiteNextAir>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SiteNextAir {
+    pub site: Site,
+    #[serde(flatten)]
+    pub next: NextAir,
+}
+
+impl Upcoming {
+    pub fn new(item: &BangumiItem, at: DateTime<Utc>) -> Self {
+        let main = item.schedule().map(|s| s.next_air(at));
+        let sites = item
+            .sites
+            .iter()
+            .filter_map(|site| {
+                Some(SiteNextAir {
+                    site: site.site.clone(),
+                    next: item.site_schedule(site)?.next_air(at),
+                })
+            })
+            .collect();
+        Self { main, sites }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct Pagination {
+    pub page: u64,
+    pub limit: u64,
+    pub total: u64,
+}
 
 #[derive(serde::Serialize)]
 pub struct OnAirResponse {
     pub data: Vec<Item>,
+    #[serde(flatten)]
+    pub pagination: Option<Pagination>,
 }
 
 impl OnAirResponse {
     pub fn empty() -> Self {
-        Self { data: Vec::new() }
+        Self {
+            data: Vec::new(),
+            pagination: None,
+        }
+    }
+
+    /// Build the response, optionally expanding sites and computing the
+    /// next airing of every item.
+    pub async fn build(items: Vec<OnAir>, next: bool, expand: bool) -> crate::Result<Self> {
+        let site_meta = if expand {
+            Some(service::onair::site_meta().await?)
+        } else {
+            None
+        };
+        let now = Utc::now();
+        let data = items
+            .into_iter()
+            .map(|model| {
+                let id = model.subject;
+                let mut view = ItemView::from(model);
+                if let Some(site_meta) = &site_meta {
+                    view = view.expand(site_meta);
+                }
+                Item((id, if next { view.with_next(now) } else { view }))
+            })
+            .collect();
+        Ok(Self {
+            data,
+            pagination: None,
+        })
     }
-}
 
-impl From<BangumiItemMap> for OnAirResponse {
-    fn from(map: BangumiItemMap) -> Self {
-        let data = map.into_iter().map(|(id, item)| Item((id, item))).collect();
-        Self { data }
+    pub fn paginate(mut self, page: u64, limit: u64, total: u64) -> Self {
+        self.pagination = Some(Pagination { page, limit, total });
+        self
     }
 }
 
 #[derive(serde::Deserialize)]
 pub struct OnAirQuery {
-    #[serde(deserialize_with = "OnAirQuery::deserialize_subjects")]
-    subjects: SubjectIds,
+    #[serde(default, deserialize_with = "OnAirQuery::deserialize_subjects_opt")]
+    subjects: Option<SubjectIds>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    begin_after: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    begin_before: Option<DateTime<Utc>>,
+    #[serde(rename = "type")]
+    item_type: Option<ItemType>,
+    lang: Option<Language>,
+    page: Option<u64>,
+    limit: Option<u64>,
+    #[serde(default)]
+    next: bool,
+    #[serde(default)]
+    expand: bool,
+    #[serde(default)]
+    include_removed: bool,
 }
 
 impl OnAirQuery {
@@ -43,15 +244,508 @@ impl OnAirQuery {
         let subjects = s.split(',').filter_map(|v| v.parse().ok()).collect();
         Ok(subjects)
     }
+
+    fn deserialize_subjects_opt<'de, D>(deserializer: D) -> Result<Option<SubjectIds>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Self::deserialize_subjects(deserializer).map(Some)
+    }
 }
 
+/// 1-based page and page size, clamped to `MAX_PAGE_LIMIT`.
+fn page_and_limit(page: Option<u64>, limit: Option<u64>) -> (u64, u64) {
+    let page = page.unwrap_or(1).max(1);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+    (page, limit)
+}
+
+async fn list_by_filter(
+    filter: &service::onair::Filter,
+    page: Option<u64>,
+    limit: Option<u64>,
+    next: bool,
+    expand: bool,
+) -> crate::Result<OnAirResponse> {
+    let (page, limit) = page_and_limit(page, limit);
+    let (items, total) = service::onair::find_by_filter(filter, page - 1, limit).await?;
+    let response = OnAirResponse::build(items, next, expand).await?;
+    Ok(response.paginate(page, limit, total))
+}
+
+/// Look up `subjects`, or list items matching the filters when no
+/// `subjects` is given. Tombstoned items are hidden unless `include_removed`.
 #[axum::debug_handler]
 pub async fn query_by_subjects(
-    Query(OnAirQuery { subjects }): Query<OnAirQuery>,
+    Query(query): Query<OnAirQuery>,
 ) -> crate::Result<Json<OnAirResponse>> {
+    let Some(subjects) = query.subjects else {
+        let mut filter = service::onair::Filter::default();
+        filter
+            .set_begin_after(query.begin_after)
+            .set_begin_before(query.begin_before)
+            .set_item_type(query.item_type)
+            .set_lang(query.lang)
+            .set_include_removed(query.include_removed);
+        let response =
+            list_by_filter(&filter, query.page, query.limit, query.next, query.expand).await?;
+        return Ok(Json(response));
+    };
     if subjects.is_empty() {
         return Ok(Json(OnAirResponse::empty()));
     }
-    let data = service::onair::find_by_subject_ids(&subjects).await?;
-    Ok(Json(data.into()))
+    let mut data =
+        service::onair::find_models_by_subject_ids(&subjects, query.include_removed).await?;
+    data.sort_by_key(|m| m.subject);
+    let response = OnAirResponse::build(data, query.next, query.expand);
+    Ok(Json(response.await?))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SeasonQuery {
+    #[serde(rename = "type")]
+    item_type: Option<ItemType>,
+    lang: Option<Language>,
+    page: Option<u64>,
+    limit: Option<u64>,
+    #[serde(default)]
+    next: bool,
+    #[serde(default)]
+    expand: bool,
+    #[serde(default)]
+    include_removed: bool,
+}
+
+/// Items beginning in a season. Quarters start in January, April, July and
+/// October, in Japan time as the broadcasters schedule them.
+#[axum::debug_handler]
+pub async fn query_by_season(
+    Path((year, quarter)): Path<(i32, u32)>,
+    Query(query): Query<SeasonQuery>,
+) -> crate::Result<Json<OnAirResponse>> {
+    if !(1..=4).contains(&quarter) {
+        return Err(crate::error::Error::bad_request("quarter must be 1 to 4"));
+    }
+    let jst = FixedOffset::east_opt(9 * 3600).unwrap();
+    let start = NaiveDate::from_ymd_opt(year, quarter * 3 - 2, 1)
+        .ok_or_else(|| crate::error::Error::bad_request("invalid year"))?;
+    let range = DateRange {
+        from: start,
+        to: start + Months::new(3) - Days::new(1),
+        tz: jst,
+    };
+    let mut filter = service::onair::Filter::default();
+    filter
+        .set_begin_after(Some(range.start()))
+        .set_begin_before(Some(range.end()))
+        .set_item_type(query.item_type)
+        .set_lang(query.lang)
+        .set_include_removed(query.include_removed);
+    let response =
+        list_by_filter(&filter, query.page, query.limit, query.next, query.expand).await?;
+    Ok(Json(response))
+}
+
+#[derive(serde::Deserialize)]
+pub struct SearchQuery {
+    q: String,
+    limit: Option<u64>,
+    #[serde(default)]
+    next: bool,
+    #[serde(default)]
+    expand: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct Match {
+    pub subject: SubjectId,
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    pub score: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct SearchResponse {
+    #[serde(flatten)]
+    pub items: OnAirResponse,
+    pub matches: Vec<Match>,
+}
+
+/// Search original and translated titles. `data` and `matches` are in the
+/// same order: exact, prefix, substring, then fuzzy matches.
+#[axum::debug_handler]
+pub async fn search(Query(query): Query<SearchQuery>) -> crate::Result<Json<SearchResponse>> {
+    if query.q.trim().is_empty() {
+        return Err(crate::error::Error::bad_request("`q` is empty"));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let hits = service::onair::search(&query.q, limit).await?;
+    let subjects = hits.iter().map(|hit| hit.subject).collect();
+    let mut found = service::onair::find_models_by_subject_ids(&subjects, false)
+        .await?
+        .into_iter()
+        .map(|m| (m.subject, m))
+        .collect::<HashMap<_, _>>();
+    let (items, matches) = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let item = found.remove(&hit.subject)?;
+            let m = Match {
+                subject: hit.subject,
+                kind: hit.kind,
+                score: hit.score,
+            };
+            Some((item, m))
+        })
+        .unzip();
+    let items = OnAirResponse::build(items, query.next, query.expand).await?;
+    Ok(Json(SearchResponse { items, matches }))
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub id: i64,
+    pub subject: SubjectId,
+    pub kind: ChangeKind,
+    #[serde(flatten)]
+    pub diff: Diff,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<OnAirChange> for Change {
+    fn from(model: OnAirChange) -> Self {
+        Self {
+            id: model.id,
+            subject: model.subject,
+            kind: model.kind,
+            diff: model.diff,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct ChangesResponse {
+    pub data: Vec<Change>,
+    /// Pass as `after` to fetch the following changes, absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ChangesQuery {
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    since: Option<DateTime<Utc>>,
+    after: Option<i64>,
+    kind: Option<ChangeKind>,
+    #[serde(default)]
+    sites_added: bool,
+    limit: Option<u64>,
+}
+
+/// Changes recorded by on-air refreshes, oldest first.
+#[axum::debug_handler]
+pub async fn query_changes(
+    Query(query): Query<ChangesQuery>,
+) -> crate::Result<Json<ChangesResponse>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+    let mut filter = service::onair::ChangeFilter::default();
+    filter
+        .set_since(query.since)
+        .set_after(query.after)
+        .set_kind(query.kind)
+        .set_sites_added(query.sites_added);
+    let changes = service::onair::find_changes(&filter, limit).await?;
+    let next = changes
+        .last()
+        .filter(|_| changes.len() as u64 == limit)
+        .map(|c| c.id);
+    let data = changes.into_iter().map(Change::from).collect();
+    Ok(Json(ChangesResponse { data, next }))
+}
+
+#[derive(serde::Serialize)]
+pub struct HistoryResponse {
+    pub data: Vec<Change>,
+}
+
+#[axum::debug_handler]
+pub async fn query_history(Path(id): Path<SubjectId>) -> crate::Result<Json<HistoryResponse>> {
+    let data = service::onair::find_history(id)
+        .await?
+        .into_iter()
+        .map(Change::from)
+        .collect();
+    Ok(Json(HistoryResponse { data }))
+}
+
+/// Render every site's `url` from `siteMeta`.
+pub async fn expand_sites(items: impl IntoIterator<Item = &mut BangumiItem>) -> anyhow::Result<()> {
+    let site_meta = service::onair::site_meta().await?;
+    for site in items.into_iter().flat_map(|item| item.sites.iter_mut()) {
+        if let Some(meta) = site_meta.get(&site.site) {
+            site.expand(meta);
+        }
+    }
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct SitesResponse {
+    pub data: SiteMetaMap,
+}
+
+#[axum::debug_handler]
+pub async fn query_sites() -> crate::Result<Json<SitesResponse>> {
+    let data = service::onair::site_meta().await?;
+    Ok(Json(SitesResponse { data }))
+}
+
+#[derive(serde::Serialize)]
+pub struct Slot {
+    pub subject: SubjectId,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<Site>,
+    pub episode: u32,
+    pub time: DateTime<FixedOffset>,
+}
+
+#[derive(serde::Serialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub slots: Vec<Slot>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CalendarResponse {
+    pub data: Vec<Day>,
+}
+
+/// Inclusive range of local dates in a fixed timezone.
+#[derive(Debug, Clone, Copy)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub tz: FixedOffset,
+}
+
+impl DateRange {
+    fn midnight(&self, date: NaiveDate) -> DateTime<Utc> {
+        date.and_time(NaiveTime::MIN)
+            .and_local_timezone(self.tz)
+            .single()
+            .expect("fixed offsets are never ambiguous")
+            .with_timezone(&Utc)
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.midnight(self.from)
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.midnight(self.to + Days::new(1))
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.from.iter_days().take_while(|d| *d <= self.to)
+    }
+}
+
+/// Expand the broadcasts of `items` into slots grouped by local day. The
+/// main broadcast is always included, plus the broadcasts of `sites`.
+pub fn build_calendar(items: &BangumiItemMap, range: &DateRange, sites: &[Site]) -> Vec<Day> {
+    let (start, end) = (range.start(), range.end());
+    let mut slots = Vec::new();
+    for (subject, item) in items {
+        let schedules = item.schedule().map(|s| (None, s)).into_iter().chain(
+            item.sites
+                .iter()
+                .filter(|s| sites.contains(&s.site))
+                .filter_map(|s| Some((Some(s.site.clone()), item.site_schedule(s)?))),
+        );
+        for (site, schedule) in schedules {
+            slots.extend(schedule.between(start, end).map(|airing| Slot {
+                subject: *subject,
+                title: item.title.clone(),
+                site: site.clone(),
+                episode: airing.episode,
+                time: airing.time.with_timezone(&range.tz),
+            }));
+        }
+    }
+    slots.sort_by_key(|slot| (slot.time, slot.subject));
+    let mut days = range
+        .days()
+        .map(|date| Day {
+            date,
+            slots: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    for slot in slots {
+        let date = slot.time.date_naive();
+        if let Some(day) = days.iter_mut().find(|d| d.date == date) {
+            day.slots.push(slot);
+        }
+    }
+    days
+}
+
+#[derive(serde::Deserialize)]
+pub struct CalendarQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_tz")]
+    tz: Option<FixedOffset>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_list")]
+    sites: Vec<Site>,
+}
+
+impl CalendarQuery {
+    fn range(&self) -> crate::Result<DateRange> {
+        let tz = self.tz.unwrap_or(Utc.fix());
+        let from = self
+            .from
+            .unwrap_or_else(|| Utc::now().with_timezone(&tz).date_naive());
+        let to = self.to.unwrap_or(from + Days::new(6));
+        if to < from {
+            return Err(crate::error::Error::bad_request("`to` is before `from`"));
+        }
+        if (to - from).num_days() as u64 >= MAX_CALENDAR_DAYS {
+            return Err(crate::error::Error::bad_request(format!(
+                "range exceeds {MAX_CALENDAR_DAYS} days"
+            )));
+        }
+        Ok(DateRange { from, to, tz })
+    }
+}
+
+#[axum::debug_handler]
+pub async fn query_calendar(
+    Query(query): Query<CalendarQuery>,
+) -> crate::Result<Json<CalendarResponse>> {
+    let range = query.range()?;
+    let items = service::onair::find_by_date_range(range.start(), range.end()).await?;
+    let data = build_calendar(&items, &range, &query.sites);
+    Ok(Json(CalendarResponse { data }))
+}
+
+/// One recurring event per subject for the main broadcast, plus one per
+/// requested site with its own broadcast.
+pub fn build_events(items: &BangumiItemMap, sites: &[Site], lang: Option<&str>) -> Vec<Event> {
+    let mut subjects = items.keys().collect::<Vec<_>>();
+    subjects.sort();
+    let mut events = Vec::new();
+    for subject in subjects {
+        let item = &items[subject];
+        let title = item.localized_title(lang);
+        if let Some(schedule) = item.schedule() {
+            let id = format!("onair-{subject}");
+            if let Some(event) = Event::recurring(&id, title.to_string(), &schedule) {
+                events.push(event.url(Some(item.official_site.clone())));
+            }
+        }
+        for site in item.sites.iter().filter(|s| sites.contains(&s.site)) {
+            let Some(schedule) = item.site_schedule(site) else {
+                continue;
+            };
+            let id = format!("onair-{subject}-{}", site.site);
+            let summary = format!("{title} ({})", site.site);
+            if let Some(event) = Event::recurring(&id, summary, &schedule) {
+                events.push(event.url(site.url.clone()));
+            }
+        }
+    }
+    events
+}
+
+#[derive(serde::Deserialize)]
+pub struct CalendarIcsQuery {
+    #[serde(deserialize_with = "OnAirQuery::deserialize_subjects")]
+    subjects: SubjectIds,
+    #[serde(default, deserialize_with = "crate::query::deserialize_list")]
+    sites: Vec<Site>,
+    lang: Option<String>,
+}
+
+#[axum::debug_handler]
+pub async fn query_calendar_ics(
+    Query(CalendarIcsQuery {
+        subjects,
+        sites,
+        lang,
+    }): Query<CalendarIcsQuery>,
+) -> crate::Result<Calendar> {
+    if subjects.is_empty() {
+        return Err(crate::error::Error::bad_request("`subjects` is required"));
+    }
+    let mut items = service::onair::find_by_subject_ids(&subjects).await?;
+    if !sites.is_empty() {
+        expand_sites(items.values_mut()).await?;
+    }
+    Ok(Calendar {
+        name: "OnAir".to_string(),
+        events: build_events(&items, &sites, lang.as_deref()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_expand() {
+        let item: BangumiItem = serde_json::from_value(json!({
+            "title": "瑠璃の宝石",
+            "titleTranslate": {},
+            "type": "tv",
+            "lang": "ja",
+            "officialSite": "https://rurinohouseki.com/",
+            "begin": "2025-07-06T12:00:00.000Z",
+            "end": "",
+            "sites": [{ "site": "bilibili", "id": "28339649" }],
+        }))
+        .unwrap();
+        let site_meta: SiteMetaMap = serde_json::from_value(json!({
+            "bilibili": {
+                "title": "哔哩哔哩",
+                "urlTemplate": "https://www.bilibili.com/bangumi/media/md{{id}}/",
+                "regions": ["CN"],
+                "type": "onair",
+            },
+        }))
+        .unwrap();
+        let view = |view: ItemView| serde_json::to_value(view).unwrap()["sites"][0].clone();
+        let model = |data| OnAir {
+            subject: 512190,
+            data,
+            begin: None,
+            end: None,
+            item_type: ItemType::Tv,
+            lang: Language::Ja,
+            removed_at: None,
+        };
+        assert_eq!(
+            json!({ "site": "bilibili", "id": "28339649" }),
+            view(ItemView::from(model(item.clone())))
+        );
+        assert_eq!(
+            json!({
+                "site": "bilibili",
+                "id": "28339649",
+                "url": "https://www.bilibili.com/bangumi/media/md28339649/",
+                "regions": ["CN"],
+                "type": "onair",
+            }),
+            view(ItemView::from(model(item)).expand(&site_meta))
+        );
+    }
 }
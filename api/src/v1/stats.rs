@@ -0,0 +1,94 @@
+use crate::AppState;
+use crate::v1::onair::DateRange;
+
+use axum::{Json, Router, extract::Query, routing::get};
+use chrono::{Days, FixedOffset, NaiveDate, Offset, Utc};
+use model::prelude::UserState;
+
+const DEFAULT_STATS_DAYS: u64 = 30;
+const MAX_STATS_DAYS: u64 = 366;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/user-states", get(query_user_states))
+}
+
+#[derive(serde::Deserialize)]
+pub struct UserStatesQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_tz")]
+    tz: Option<FixedOffset>,
+}
+
+impl UserStatesQuery {
+    fn range(&self) -> crate::Result<DateRange> {
+        let tz = self.tz.unwrap_or(Utc.fix());
+        let to = self
+            .to
+            .unwrap_or_else(|| Utc::now().with_timezone(&tz).date_naive());
+        let from = self.from.unwrap_or(to - Days::new(DEFAULT_STATS_DAYS - 1));
+        if to < from {
+            return Err(crate::error::Error::bad_request("`to` is before `from`"));
+        }
+        if (to - from).num_days() as u64 >= MAX_STATS_DAYS {
+            return Err(crate::error::Error::bad_request(format!(
+                "range exceeds {MAX_STATS_DAYS} days"
+            )));
+        }
+        Ok(DateRange { from, to, tz })
+    }
+}
+
+/// Users who moved into each state on a day.
+#[derive(serde::Serialize)]
+pub struct StateDay {
+    pub date: NaiveDate,
+    pub active: i64,
+    pub abondon: i64,
+    pub dropped: i64,
+    pub banned: i64,
+}
+
+impl StateDay {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            active: 0,
+            abondon: 0,
+            dropped: 0,
+            banned: 0,
+        }
+    }
+
+    fn count_mut(&mut self, state: &UserState) -> &mut i64 {
+        match state {
+            UserState::Active => &mut self.active,
+            UserState::Abondon => &mut self.abondon,
+            UserState::Dropped => &mut self.dropped,
+            UserState::Banned => &mut self.banned,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct UserStatesResponse {
+    pub data: Vec<StateDay>,
+}
+
+/// State transitions per local day, every day of the range included.
+#[axum::debug_handler]
+pub async fn query_user_states(
+    Query(query): Query<UserStatesQuery>,
+) -> crate::Result<Json<UserStatesResponse>> {
+    let range = query.range()?;
+    let offset = range.tz.local_minus_utc();
+    let counts = service::user::count_states_by_day(range.start(), range.end(), offset).await?;
+    let mut data = range.days().map(StateDay::new).collect::<Vec<_>>();
+    for count in counts {
+        let Some(day) = data.iter_mut().find(|d| d.date == count.day) else {
+            continue;
+        };
+        *day.count_mut(&count.state) += count.count;
+    }
+    Ok(Json(UserStatesResponse { data }))
+}
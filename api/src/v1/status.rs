@@ -0,0 +1,73 @@
+use crate::AppState;
+
+use axum::{Json, Router, routing::get};
+use fetcher::health::{CircuitState, OriginStatus};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/origins", get(query_origins))
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Circuit {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl From<CircuitState> for Circuit {
+    fn from(state: CircuitState) -> Self {
+        match state {
+            CircuitState::Closed => Self::Closed,
+            CircuitState::Open => Self::Open,
+            CircuitState::HalfOpen => Self::HalfOpen,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct Origin {
+    pub origin: String,
+    pub circuit: Circuit,
+    pub requests: u64,
+    pub failures: u64,
+    pub success_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl From<OriginStatus> for Origin {
+    fn from(status: OriginStatus) -> Self {
+        Self {
+            origin: status.origin,
+            circuit: status.circuit.into(),
+            requests: status.requests,
+            failures: status.failures,
+            success_rate: status.success_rate,
+            latency_ms: status.latency.map(|d| d.as_millis() as u64),
+            consecutive_failures: status.consecutive_failures,
+            retry_in_secs: status.retry_in.map(|d| d.as_secs()),
+            last_error: status.last_error,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct OriginsResponse {
+    pub data: Vec<Origin>,
+}
+
+/// Health of every origin the fetchers have picked or requested since start.
+#[axum::debug_handler]
+pub async fn query_origins() -> Json<OriginsResponse> {
+    let data = fetcher::health::origin_status()
+        .into_iter()
+        .map(Origin::from)
+        .collect();
+    Json(OriginsResponse { data })
+}
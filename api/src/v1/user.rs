@@ -1,10 +1,44 @@
 use crate::AppState;
+use crate::ical::Calendar;
+use crate::v1::onair::{DateRange, Day, build_calendar, build_events, expand_sites};
 
-use axum::{Json, Router, extract::Query, routing::get};
-use model::prelude::{Collections, NameHistory, Uid, User, UserState};
+use axum::{
+    Json, Router,
+    extract::{Path, Query},
+    routing::{get, post},
+};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Offset, Utc};
+use collector::user::{CollectionList, Lookup, RefreshJob, RefreshState};
+use model::common::onair::{BangumiItemMap, Site};
+use model::common::text::MatchKind;
+use model::prelude::{
+    Collections, NameHistory, Profile, ProfileField, SubjectId, SubjectState, SubjectType, Uid,
+    User, UserCollection, UserCollectionSnapshot, UserProfileHistory, UserSort, UserState,
+    UserStateLog, Uuid,
+};
+
+const DEFAULT_BY_NAME_LIMIT: u64 = 50;
+const MAX_BY_NAME_LIMIT: u64 = 200;
+const MAX_BATCH_SIZE: usize = 100;
+const DEFAULT_LIST_LIMIT: u64 = 50;
+const MAX_LIST_LIMIT: u64 = 500;
+const DEFAULT_SUGGESTIONS: u64 = 20;
+const MAX_SUGGESTIONS: u64 = 100;
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/name-history", get(query_name_history_by_uid))
+    Router::new()
+        .route("/name-history", get(query_name_history_by_uid))
+        .route("/by-name", get(query_by_name))
+        .route("/batch", post(query_batch))
+        .route("/refresh", post(refresh_user))
+        .route("/refresh/{job}", get(query_refresh))
+        .route("/collections", get(query_collections))
+        .route("/collections/history", get(query_collection_history))
+        .route("/friends", get(query_friends))
+        .route("/schedule", get(query_schedule))
+        .route("/schedule.ics", get(query_schedule_ics))
+        .route("/state-history", get(query_state_history))
+        .route("/profile/history", get(query_profile_history))
 }
 
 #[derive(serde::Serialize)]
@@ -35,6 +69,8 @@ pub struct Data {
     pub name_history: Option<NameHistory>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub collections: Option<Collections>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
 }
 
 impl From<User> for Data {
@@ -49,6 +85,7 @@ impl From<User> for Data {
             update_at: user.update_at,
             name_history: user.extra.name_history,
             collections: user.extra.collections,
+            profile: user.extra.profile,
         }
     }
 }
@@ -77,3 +114,724 @@ pub async fn query_name_history_by_uid(
     let user = collector::user::query_user(uid).await?;
     return Ok(Json(user.into()));
 }
+
+#[derive(serde::Deserialize)]
+pub struct ByNameQuery {
+    name: String,
+    #[serde(default)]
+    fuzzy: bool,
+    limit: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct NameMatch {
+    pub name: String,
+    #[serde(rename = "match")]
+    pub kind: MatchKind,
+    pub score: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct ByNameData {
+    #[serde(flatten)]
+    pub user: Data,
+    pub matches: Vec<NameMatch>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ByNameResponse {
+    pub data: Vec<ByNameData>,
+}
+
+/// Users whose current or past names match `name`. `limit` caps the matched
+/// names, so fewer users may be returned.
+#[axum::debug_handler]
+pub async fn query_by_name(
+    Query(query): Query<ByNameQuery>,
+) -> crate::Result<Json<ByNameResponse>> {
+    if query.name.trim().is_empty() {
+        return Err(crate::error::Error::bad_request("`name` is empty"));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_BY_NAME_LIMIT)
+        .clamp(1, MAX_BY_NAME_LIMIT);
+    let users = service::user::find_by_name(&query.name, query.fuzzy, limit).await?;
+    let data = users
+        .into_iter()
+        .map(|(user, hits)| ByNameData {
+            user: user.into(),
+            matches: hits
+                .into_iter()
+                .map(|hit| NameMatch {
+                    name: hit.name,
+                    kind: hit.kind,
+                    score: hit.score,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(Json(ByNameResponse { data }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct CollectionHistoryQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    from: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    to: Option<DateTime<Utc>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CollectionSnapshot {
+    pub time: DateTime<Utc>,
+    pub collections: Collections,
+}
+
+impl From<UserCollectionSnapshot> for CollectionSnapshot {
+    fn from(snapshot: UserCollectionSnapshot) -> Self {
+        Self {
+            time: snapshot.created_at,
+            collections: snapshot.collections,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct CollectionHistoryResponse {
+    pub data: Vec<CollectionSnapshot>,
+}
+
+/// Collection counts of a known user each time they changed, oldest first.
+#[axum::debug_handler]
+pub async fn query_collection_history(
+    Query(query): Query<CollectionHistoryQuery>,
+) -> crate::Result<Json<CollectionHistoryResponse>> {
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && to < from
+    {
+        return Err(crate::error::Error::bad_request("`to` is before `from`"));
+    }
+    let Some(user) = service::user::find_by_uid(query.uid).await? else {
+        return Err(crate::error::Error::not_found("user not found"));
+    };
+    let data = service::user::find_collection_history(&user, query.from, query.to)
+        .await?
+        .into_iter()
+        .map(CollectionSnapshot::from)
+        .collect();
+    Ok(Json(CollectionHistoryResponse { data }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct BatchRequest {
+    #[serde(deserialize_with = "BatchRequest::deserialize_uids")]
+    uids: Vec<Uid>,
+}
+
+impl BatchRequest {
+    /// Accept both `"sai"` and `1` style uids.
+    fn deserialize_uids<'de, D>(deserializer: D) -> Result<Vec<Uid>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Nid(model::common::user::Nid),
+            Str(String),
+        }
+        let raw: Vec<Raw> = serde::Deserialize::deserialize(deserializer)?;
+        Ok(raw
+            .into_iter()
+            .map(|raw| match raw {
+                Raw::Nid(nid) => Uid::Nid(nid),
+                Raw::Str(s) => Uid::from_str(s.trim()),
+            })
+            .collect())
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Fresh,
+    Stale,
+    Queued,
+    NotFound,
+}
+
+#[derive(serde::Serialize)]
+pub struct BatchItem {
+    pub uid: String,
+    pub status: BatchStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<Data>,
+}
+
+impl BatchItem {
+    fn new(uid: Uid, lookup: Lookup) -> Self {
+        let (status, user) = match lookup {
+            Lookup::Fresh(user) => (BatchStatus::Fresh, Some(user)),
+            Lookup::Stale(user) => (BatchStatus::Stale, Some(user)),
+            Lookup::Queued(user) => (BatchStatus::Queued, user),
+            Lookup::NotFound => (BatchStatus::NotFound, None),
+        };
+        Self {
+            uid: uid.to_string(),
+            status,
+            user: user.map(Data::from),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct BatchResponse {
+    pub data: Vec<BatchItem>,
+}
+
+/// Cached users for many uids at once. Never waits for a fetch: expired users
+/// are refreshed in the background and unknown ones are reported as such.
+#[axum::debug_handler]
+pub async fn query_batch(Json(request): Json<BatchRequest>) -> crate::Result<Json<BatchResponse>> {
+    let mut uids: Vec<Uid> = Vec::with_capacity(request.uids.len());
+    for uid in request.uids {
+        if !uids.contains(&uid) {
+            uids.push(uid);
+        }
+    }
+    if uids.is_empty() {
+        return Err(crate::error::Error::bad_request("`uids` is empty"));
+    }
+    if uids.len() > MAX_BATCH_SIZE {
+        return Err(crate::error::Error::bad_request(format!(
+            "at most {MAX_BATCH_SIZE} uids per batch"
+        )));
+    }
+    if uids
+        .iter()
+        .any(|uid| matches!(uid, Uid::Sid(sid) if sid.is_empty()))
+    {
+        return Err(crate::error::Error::bad_request(
+            "`uids` contains an empty uid",
+        ));
+    }
+    let data = collector::user::query_users(uids)
+        .await?
+        .into_iter()
+        .map(|(uid, lookup)| BatchItem::new(uid, lookup))
+        .collect();
+    Ok(Json(BatchResponse { data }))
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(serde::Serialize)]
+pub struct RefreshUpdated {
+    pub profile: bool,
+    pub name_history: bool,
+}
+
+#[derive(serde::Serialize)]
+pub struct RefreshData {
+    pub job: Uuid,
+    pub uid: String,
+    pub status: RefreshStatus,
+    pub started_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<RefreshUpdated>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<Data>,
+}
+
+impl From<RefreshJob> for RefreshData {
+    fn from(job: RefreshJob) -> Self {
+        let mut data = Self {
+            job: job.id,
+            uid: job.uid.to_string(),
+            status: RefreshStatus::Running,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+            updated: None,
+            error: None,
+            user: None,
+        };
+        match job.state {
+            RefreshState::Running => {}
+            RefreshState::Done {
+                profile,
+                name_history,
+                user,
+            } => {
+                data.status = RefreshStatus::Done;
+                data.updated = Some(RefreshUpdated {
+                    profile,
+                    name_history,
+                });
+                data.user = Some((*user).into());
+            }
+            RefreshState::Failed(error) => {
+                data.status = RefreshStatus::Failed;
+                data.error = Some(error);
+            }
+        }
+        data
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct RefreshResponse {
+    pub data: RefreshData,
+}
+
+/// Start scraping a user again regardless of how fresh the cached data is.
+#[axum::debug_handler]
+pub async fn refresh_user(
+    Query(NameHistoryQuery { uid }): Query<NameHistoryQuery>,
+) -> crate::Result<Json<RefreshResponse>> {
+    if matches!(&uid, Uid::Sid(sid) if sid.is_empty()) {
+        return Err(crate::error::Error::bad_request("`uid` is empty"));
+    }
+    let job = collector::user::refresh_user(uid);
+    Ok(Json(RefreshResponse { data: job.into() }))
+}
+
+#[axum::debug_handler]
+pub async fn query_refresh(Path(job): Path<Uuid>) -> crate::Result<Json<RefreshResponse>> {
+    let Some(job) = collector::user::refresh_status(job) else {
+        return Err(crate::error::Error::not_found("refresh job not found"));
+    };
+    Ok(Json(RefreshResponse { data: job.into() }))
+}
+
+#[derive(serde::Serialize)]
+pub struct StateChange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<UserState>,
+    pub to: UserState,
+    pub observed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl From<UserStateLog> for StateChange {
+    fn from(log: UserStateLog) -> Self {
+        Self {
+            from: log.from_state,
+            to: log.to_state,
+            observed_at: log.observed_at,
+            source: log.source,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct StateHistoryResponse {
+    pub data: Vec<StateChange>,
+}
+
+/// Every state a known user has been seen in, oldest first.
+#[axum::debug_handler]
+pub async fn query_state_history(
+    Query(NameHistoryQuery { uid }): Query<NameHistoryQuery>,
+) -> crate::Result<Json<StateHistoryResponse>> {
+    let Some(user) = service::user::find_by_uid(uid).await? else {
+        return Err(crate::error::Error::not_found("user not found"));
+    };
+    let data = service::user::find_state_history(&user)
+        .await?
+        .into_iter()
+        .map(StateChange::from)
+        .collect();
+    Ok(Json(StateHistoryResponse { data }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct ProfileHistoryQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    field: Option<ProfileField>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ProfileValue {
+    pub field: ProfileField,
+    /// Absent when the field was cleared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl From<UserProfileHistory> for ProfileValue {
+    fn from(record: UserProfileHistory) -> Self {
+        Self {
+            field: record.field,
+            value: record.value,
+            first_seen: record.first_seen,
+            last_seen: record.last_seen,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct ProfileHistoryResponse {
+    pub data: Vec<ProfileValue>,
+}
+
+/// Every value the profile fields of a known user have had, oldest first.
+#[axum::debug_handler]
+pub async fn query_profile_history(
+    Query(query): Query<ProfileHistoryQuery>,
+) -> crate::Result<Json<ProfileHistoryResponse>> {
+    let Some(user) = service::user::find_by_uid(query.uid).await? else {
+        return Err(crate::error::Error::not_found("user not found"));
+    };
+    let data = service::user::find_profile_history(&user, query.field)
+        .await?
+        .into_iter()
+        .map(ProfileValue::from)
+        .collect();
+    Ok(Json(ProfileHistoryResponse { data }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct CollectionsQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    #[serde(rename = "type")]
+    subject_type: SubjectType,
+    #[serde(default, deserialize_with = "crate::query::deserialize_parsed")]
+    state: Option<SubjectState>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CollectionEntry {
+    pub subject_id: SubjectId,
+    pub state: SubjectState,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i16>,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marked_at: Option<DateTime<Utc>>,
+}
+
+impl From<UserCollection> for CollectionEntry {
+    fn from(item: UserCollection) -> Self {
+        Self {
+            subject_id: item.subject_id,
+            state: item.state,
+            title: item.title,
+            subtitle: item.subtitle,
+            rating: item.rating,
+            tags: item.tags.0,
+            comment: item.comment,
+            marked_at: item.marked_at,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct CollectionsResponse {
+    pub data: Vec<CollectionEntry>,
+    /// Oldest sync time of the returned lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+/// Subjects a user has collected, most recently marked first. Lists never
+/// fetched before are scraped in the background.
+#[axum::debug_handler]
+pub async fn query_collections(
+    Query(query): Query<CollectionsQuery>,
+) -> crate::Result<Json<CollectionsResponse>> {
+    let list =
+        collector::user::query_collection_list(query.uid, query.subject_type, query.state).await?;
+    let data = list.items.into_iter().map(CollectionEntry::from).collect();
+    Ok(Json(CollectionsResponse {
+        data,
+        synced_at: list.synced_at,
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct FriendsQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    suggestions: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+pub struct FriendEntry {
+    pub uid: String,
+    pub name: String,
+    /// Whether the friend lists the user back, only known for tracked users.
+    pub mutual: bool,
+    pub first_seen: DateTime<Utc>,
+}
+
+#[derive(serde::Serialize)]
+pub struct SuggestionEntry {
+    pub uid: String,
+    pub name: String,
+    /// How many friends of the user list this one.
+    pub common: i64,
+}
+
+impl From<service::user::FriendSuggestion> for SuggestionEntry {
+    fn from(suggestion: service::user::FriendSuggestion) -> Self {
+        Self {
+            uid: suggestion.uid,
+            name: suggestion.name,
+            common: suggestion.common,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct FriendsResponse {
+    pub data: Vec<FriendEntry>,
+    pub suggestions: Vec<SuggestionEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+/// Friends of a user, which of them list the user back, and friends of
+/// friends the user may know.
+#[axum::debug_handler]
+pub async fn query_friends(
+    Query(query): Query<FriendsQuery>,
+) -> crate::Result<Json<FriendsResponse>> {
+    let limit = query
+        .suggestions
+        .unwrap_or(DEFAULT_SUGGESTIONS)
+        .min(MAX_SUGGESTIONS);
+    let user = collector::user::query_friends(query.uid).await?;
+    let mutual = service::user::find_mutual_friends(&user).await?;
+    let data = service::user::find_friends(&user)
+        .await?
+        .into_iter()
+        .map(|friend| FriendEntry {
+            mutual: mutual.contains(&friend.friend_uid),
+            uid: friend.friend_uid,
+            name: friend.name,
+            first_seen: friend.first_seen,
+        })
+        .collect();
+    let suggestions = if limit > 0 {
+        service::user::find_friend_suggestions(&user, limit)
+            .await?
+            .into_iter()
+            .map(SuggestionEntry::from)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(Json(FriendsResponse {
+        data,
+        suggestions,
+        synced_at: user.extra.friends.map(|friends| friends.update_at),
+    }))
+}
+
+#[derive(serde::Deserialize)]
+pub struct ScheduleQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    #[serde(default, deserialize_with = "crate::query::deserialize_tz")]
+    tz: Option<FixedOffset>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_list")]
+    sites: Vec<Site>,
+}
+
+/// Events are in UTC, so unlike [`ScheduleQuery`] there is no `tz`.
+#[derive(serde::Deserialize)]
+pub struct ScheduleIcsQuery {
+    #[serde(deserialize_with = "NameHistoryQuery::deserialize_uid")]
+    uid: Uid,
+    #[serde(default, deserialize_with = "crate::query::deserialize_list")]
+    sites: Vec<Site>,
+    lang: Option<String>,
+}
+
+/// Monday to Sunday of the current week in `tz`.
+fn this_week(tz: FixedOffset) -> DateRange {
+    let today = Utc::now().with_timezone(&tz).date_naive();
+    let from = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    DateRange {
+        from,
+        to: from + Days::new(6),
+        tz,
+    }
+}
+
+/// On-air data of the anime a user is watching.
+async fn doing_anime(uid: Uid) -> crate::Result<(CollectionList, BangumiItemMap)> {
+    let list =
+        collector::user::query_collection_list(uid, SubjectType::Anime, Some(SubjectState::Do))
+            .await?;
+    let subjects = list.items.iter().map(|item| item.subject_id).collect();
+    let items = service::onair::find_by_subject_ids(&subjects).await?;
+    Ok((list, items))
+}
+
+#[derive(serde::Serialize)]
+pub struct ScheduleResponse {
+    pub data: Vec<Day>,
+    /// When the watching list was last scraped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synced_at: Option<DateTime<Utc>>,
+}
+
+/// This week's airing slots of the anime a user is watching.
+#[axum::debug_handler]
+pub async fn query_schedule(
+    Query(query): Query<ScheduleQuery>,
+) -> crate::Result<Json<ScheduleResponse>> {
+    let range = this_week(query.tz.unwrap_or(Utc.fix()));
+    let (list, items) = doing_anime(query.uid).await?;
+    let data = build_calendar(&items, &range, &query.sites);
+    Ok(Json(ScheduleResponse {
+        data,
+        synced_at: list.synced_at,
+    }))
+}
+
+/// The anime a user is watching as recurring events.
+#[axum::debug_handler]
+pub async fn query_schedule_ics(Query(query): Query<ScheduleIcsQuery>) -> crate::Result<Calendar> {
+    let (list, mut items) = doing_anime(query.uid).await?;
+    if !query.sites.is_empty() {
+        expand_sites(items.values_mut()).await?;
+    }
+    Ok(Calendar {
+        name: format!("OnAir - {}", list.user.name),
+        events: build_events(&items, &query.sites, query.lang.as_deref()),
+    })
+}
+
+#[derive(serde::Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListQuery {
+    #[serde(default, deserialize_with = "crate::query::deserialize_list")]
+    state: Vec<UserState>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    joined_after: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    joined_before: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    active_since: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "crate::query::deserialize_time")]
+    active_before: Option<DateTime<Utc>>,
+    name_prefix: Option<String>,
+    #[serde(default)]
+    sort: UserSort,
+    #[serde(default)]
+    order: Order,
+    cursor: Option<String>,
+    limit: Option<u64>,
+}
+
+/// Cursors are the sort key in microseconds and the id of the last user of
+/// a page, e.g. `1728646869153212_0199d2a4-8c8e-7d41-9a51-6f0f3c1f4b1e`.
+fn encode_cursor(key: DateTime<Utc>, id: Uuid) -> String {
+    format!("{}_{id}", key.timestamp_micros())
+}
+
+fn decode_cursor(cursor: &str) -> Option<(DateTime<Utc>, Uuid)> {
+    let (key, id) = cursor.split_once('_')?;
+    let key = DateTime::from_timestamp_micros(key.parse().ok()?)?;
+    let id = Uuid::parse_str(id).ok()?;
+    Some((key, id))
+}
+
+#[derive(serde::Serialize)]
+pub struct ListResponse {
+    pub data: Vec<Data>,
+    /// Pass as `cursor` to fetch the following page, absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+/// Known users matching the filters, ordered by `sort` then by id.
+#[axum::debug_handler]
+pub async fn list_users(Query(query): Query<ListQuery>) -> crate::Result<Json<ListResponse>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .clamp(1, MAX_LIST_LIMIT);
+    let after = match &query.cursor {
+        Some(cursor) => Some(
+            decode_cursor(cursor)
+                .ok_or_else(|| crate::error::Error::bad_request("invalid `cursor`"))?,
+        ),
+        None => None,
+    };
+    let mut filter = service::user::Filter::default();
+    filter
+        .set_states(query.state)
+        .set_joined(query.joined_after, query.joined_before)
+        .set_active(query.active_since, query.active_before)
+        .set_name_prefix(query.name_prefix)
+        .set_sort(query.sort, query.order == Order::Desc)
+        .set_after(after);
+    let users = service::user::find_by_filter(&filter, limit).await?;
+    let next = users
+        .last()
+        .filter(|_| users.len() as u64 == limit)
+        .and_then(|user| Some(encode_cursor(query.sort.key(user)?, user.id)));
+    let data = users.into_iter().map(Data::from).collect();
+    Ok(Json(ListResponse { data, next }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let key = DateTime::from_timestamp_micros(1728646869153212).unwrap();
+        let id = Uuid::parse_str("0199d2a4-8c8e-7d41-9a51-6f0f3c1f4b1e").unwrap();
+        let cursor = encode_cursor(key, id);
+        assert_eq!(Some((key, id)), decode_cursor(&cursor));
+        assert_eq!(None, decode_cursor("1728646869153212"));
+        assert_eq!(
+            None,
+            decode_cursor("x_0199d2a4-8c8e-7d41-9a51-6f0f3c1f4b1e")
+        );
+    }
+
+    #[test]
+    fn test_this_week() {
+        let tz = FixedOffset::east_opt(9 * 3600).unwrap();
+        let week = this_week(tz);
+        assert_eq!(chrono::Weekday::Mon, week.from.weekday());
+        assert_eq!(7, week.days().count());
+        let today = Utc::now().with_timezone(&tz).date_naive();
+        assert!(week.from <= today && today <= week.to);
+    }
+}
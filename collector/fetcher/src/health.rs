@@ -0,0 +1,319 @@
+use rand::seq::IndexedRandom;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Weight of the latest request in the moving success rate and latency.
+const ALPHA: f64 = 0.2;
+
+static HEALTH: LazyLock<Health> =
+    LazyLock::new(|| Health::new(config::get().fetcher.health.clone()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Circuit {
+    Closed,
+    /// Ejected until the instant, then half-open.
+    Open(Instant),
+    /// Waiting for one probe request to decide, started at the instant.
+    HalfOpen(Option<Instant>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone)]
+struct Origin {
+    requests: u64,
+    failures: u64,
+    success_rate: f64,
+    /// Seconds.
+    latency: Option<f64>,
+    consecutive_failures: u32,
+    circuit: Circuit,
+    last_error: Option<String>,
+}
+
+impl Default for Origin {
+    fn default() -> Self {
+        Self {
+            requests: 0,
+            failures: 0,
+            success_rate: 1.0,
+            latency: None,
+            consecutive_failures: 0,
+            circuit: Circuit::Closed,
+            last_error: None,
+        }
+    }
+}
+
+impl Origin {
+    fn record(
+        &mut self,
+        result: Result<(), String>,
+        latency: Duration,
+        now: Instant,
+        policy: &config::fetcher::Health,
+    ) {
+        let ok = result.is_ok();
+        self.requests += 1;
+        self.success_rate = ALPHA * (ok as u8 as f64) + (1.0 - ALPHA) * self.success_rate;
+        let latency = latency.as_secs_f64();
+        self.latency = Some(
+            self.latency
+                .map_or(latency, |avg| ALPHA * latency + (1.0 - ALPHA) * avg),
+        );
+        match result {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                self.circuit = Circuit::Closed;
+            }
+            Err(error) => {
+                self.failures += 1;
+                self.consecutive_failures += 1;
+                self.last_error = Some(error);
+                let trip = match self.circuit {
+                    Circuit::Closed => self.consecutive_failures >= policy.failure_threshold,
+                    Circuit::HalfOpen(_) => true,
+                    Circuit::Open(_) => false,
+                };
+                if trip {
+                    self.circuit = Circuit::Open(now + policy.open_duration());
+                }
+            }
+        }
+    }
+
+    /// Whether a request may go to this origin now, moving an open circuit
+    /// whose time is up to half-open.
+    fn available(&mut self, now: Instant, policy: &config::fetcher::Health) -> bool {
+        match self.circuit {
+            Circuit::Closed => true,
+            Circuit::Open(until) if now >= until => {
+                self.circuit = Circuit::HalfOpen(None);
+                true
+            }
+            Circuit::Open(_) => false,
+            Circuit::HalfOpen(None) => true,
+            // a probe that never reported back does not block forever
+            Circuit::HalfOpen(Some(started)) => now >= started + policy.open_duration(),
+        }
+    }
+
+    fn weight(&self) -> f64 {
+        self.success_rate.max(0.01) / (1.0 + self.latency.unwrap_or(0.0))
+    }
+}
+
+/// Health of an origin as seen by the fetchers.
+#[derive(Debug, Clone)]
+pub struct OriginStatus {
+    pub origin: String,
+    pub circuit: CircuitState,
+    pub requests: u64,
+    pub failures: u64,
+    /// Moving average of successful requests, from 0 to 1.
+    pub success_rate: f64,
+    pub latency: Option<Duration>,
+    pub consecutive_failures: u32,
+    /// Time left until an open circuit lets a probe through.
+    pub retry_in: Option<Duration>,
+    pub last_error: Option<String>,
+}
+
+/// Per-origin success rate, latency and circuit breaker shared by every
+/// client.
+pub struct Health {
+    policy: config::fetcher::Health,
+    origins: Mutex<HashMap<String, Origin>>,
+}
+
+impl Health {
+    pub fn new(policy: config::fetcher::Health) -> Self {
+        Self {
+            policy,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, origin: &str, result: Result<(), String>, latency: Duration) {
+        self.record_at(origin, result, latency, Instant::now());
+    }
+
+    fn record_at(&self, origin: &str, result: Result<(), String>, latency: Duration, now: Instant) {
+        let mut origins = self.origins.lock().unwrap();
+        let entry = origins.entry(origin.to_string()).or_default();
+        let before = entry.circuit;
+        entry.record(result, latency, now, &self.policy);
+        match (before, entry.circuit) {
+            (Circuit::Open(_), _) => {}
+            (_, Circuit::Open(_)) => tracing::warn!(
+                "Origin {} ejected after {} failures: {:?}",
+                origin,
+                entry.consecutive_failures,
+                entry.last_error
+            ),
+            (Circuit::HalfOpen(_), Circuit::Closed) => {
+                tracing::info!("Origin {} recovered", origin)
+            }
+            _ => {}
+        }
+    }
+
+    /// Pick one of `candidates` at random, weighted by success rate and
+    /// latency, skipping ejected origins. A half-open origin is handed out
+    /// once as a probe before any other. When every origin is ejected the
+    /// one closest to its probe is returned.
+    pub fn pick<'a>(&self, candidates: &'a [String]) -> Option<&'a String> {
+        self.pick_at(candidates, Instant::now())
+    }
+
+    fn pick_at<'a>(&self, candidates: &'a [String], now: Instant) -> Option<&'a String> {
+        let mut origins = self.origins.lock().unwrap();
+        let available = candidates
+            .iter()
+            .filter(|c| {
+                origins
+                    .entry(c.to_string())
+                    .or_default()
+                    .available(now, &self.policy)
+            })
+            .collect::<Vec<_>>();
+        // a probe goes out right away instead of competing with the success
+        // rate that got the origin ejected
+        let probe = available
+            .iter()
+            .find(|c| matches!(origins[**c].circuit, Circuit::HalfOpen(_)));
+        let picked = match probe {
+            Some(probe) => Ok(probe),
+            None => available.choose_weighted(&mut rand::rng(), |c| origins[*c].weight()),
+        };
+        let Ok(picked) = picked else {
+            return candidates.iter().min_by_key(|c| match origins[*c].circuit {
+                Circuit::Open(until) => until,
+                _ => now,
+            });
+        };
+        let origin = origins.get_mut(*picked).expect("registered above");
+        if let Circuit::HalfOpen(_) = origin.circuit {
+            origin.circuit = Circuit::HalfOpen(Some(now));
+        }
+        Some(*picked)
+    }
+
+    pub fn status(&self) -> Vec<OriginStatus> {
+        let now = Instant::now();
+        let origins = self.origins.lock().unwrap();
+        let mut status = origins
+            .iter()
+            .map(|(name, origin)| OriginStatus {
+                origin: name.clone(),
+                circuit: match origin.circuit {
+                    Circuit::Closed => CircuitState::Closed,
+                    Circuit::Open(until) if now < until => CircuitState::Open,
+                    _ => CircuitState::HalfOpen,
+                },
+                requests: origin.requests,
+                failures: origin.failures,
+                success_rate: origin.success_rate,
+                latency: origin.latency.map(Duration::from_secs_f64),
+                consecutive_failures: origin.consecutive_failures,
+                retry_in: match origin.circuit {
+                    Circuit::Open(until) if now < until => Some(until - now),
+                    _ => None,
+                },
+                last_error: origin.last_error.clone(),
+            })
+            .collect::<Vec<_>>();
+        status.sort_by(|a, b| a.origin.cmp(&b.origin));
+        status
+    }
+}
+
+/// Pick the origin to send the next request to, see [`Health::pick`].
+pub fn pick_origin(candidates: &[String]) -> Option<&String> {
+    HEALTH.pick(candidates)
+}
+
+pub(crate) fn get() -> &'static Health {
+    &HEALTH
+}
+
+pub fn origin_status() -> Vec<OriginStatus> {
+    HEALTH.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health() -> Health {
+        Health::new(config::fetcher::Health {
+            failure_threshold: 2,
+            open_secs: 60,
+        })
+    }
+
+    #[test]
+    fn test_circuit() {
+        let health = health();
+        let origins = vec!["https://bgm.tv".to_string(), "https://chii.in".to_string()];
+        let start = Instant::now();
+        let fail = || Err("503 Service Unavailable".to_string());
+        let latency = Duration::from_millis(100);
+        health.record_at("https://bgm.tv", fail(), latency, start);
+        assert!(health.pick_at(&origins, start).is_some());
+        health.record_at("https://bgm.tv", fail(), latency, start);
+        // ejected, only the other origin is picked
+        for _ in 0..20 {
+            assert_eq!(Some(&origins[1]), health.pick_at(&origins, start));
+        }
+        // half-open after the cooldown, one probe goes through
+        let later = start + Duration::from_secs(61);
+        let only = &origins[..1];
+        assert_eq!(Some(&origins[0]), health.pick_at(only, later));
+        let status = &health.status()[0];
+        assert_eq!(CircuitState::HalfOpen, status.circuit);
+        // a failed probe opens it again
+        health.record_at("https://bgm.tv", fail(), latency, later);
+        assert_eq!(CircuitState::Open, health.status()[0].circuit);
+        // with every origin ejected the closest to its probe is still used
+        assert_eq!(Some(&origins[0]), health.pick_at(only, later));
+        // a successful probe closes it
+        let after = later + Duration::from_secs(61);
+        health.pick_at(only, after);
+        health.record_at("https://bgm.tv", Ok(()), latency, after);
+        let status = &health.status()[0];
+        assert_eq!(CircuitState::Closed, status.circuit);
+        assert_eq!(4, status.requests);
+        assert_eq!(3, status.failures);
+        assert_eq!(0, status.consecutive_failures);
+    }
+
+    #[test]
+    fn test_probe_first() {
+        let health = health();
+        let origins = vec![
+            "https://bangumi.tv".to_string(),
+            "https://bgm.tv".to_string(),
+            "https://chii.in".to_string(),
+        ];
+        let start = Instant::now();
+        let latency = Duration::from_millis(100);
+        for _ in 0..10 {
+            health.record_at("https://bgm.tv", Err("502".to_string()), latency, start);
+            health.record_at("https://chii.in", Ok(()), latency, start);
+            health.record_at("https://bangumi.tv", Ok(()), latency, start);
+        }
+        let later = start + Duration::from_secs(61);
+        // the freshly half-open origin is the next pick, and only once
+        assert_eq!(Some(&origins[1]), health.pick_at(&origins, later));
+        for _ in 0..20 {
+            assert_ne!(Some(&origins[1]), health.pick_at(&origins, later));
+        }
+    }
+}
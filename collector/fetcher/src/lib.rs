@@ -1,5 +1,105 @@
+pub mod health;
+mod limiter;
+
+pub use limiter::RateLimiter;
+pub use reqwest::{Response, StatusCode, header};
 use std::sync::LazyLock;
-pub type Fetcher = reqwest::Client;
+
+/// Redirects followed for one request, each hop waiting for the rate limit.
+const MAX_REDIRECTS: usize = 10;
+
+/// A `reqwest::Client` whose requests, redirects included, wait for the
+/// rate limit of the client when one is configured.
+pub struct Fetcher {
+    client: reqwest::Client,
+    limiter: Option<RateLimiter>,
+}
+
+impl Fetcher {
+    pub fn get<U: reqwest::IntoUrl>(&self, url: U) -> RequestBuilder<'_> {
+        RequestBuilder {
+            fetcher: self,
+            inner: self.client.get(url),
+        }
+    }
+
+    /// Send `request` and follow its redirects, so that the final URL is
+    /// still available from the response.
+    async fn execute(&self, mut request: reqwest::Request) -> reqwest::Result<Response> {
+        let mut hops = 0;
+        loop {
+            let next = request.try_clone();
+            let response = self.execute_once(request).await?;
+            match (redirect_location(&response), next) {
+                (Some(location), Some(mut next)) if hops < MAX_REDIRECTS => {
+                    *next.url_mut() = location;
+                    request = next;
+                    hops += 1;
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    async fn execute_once(&self, request: reqwest::Request) -> reqwest::Result<Response> {
+        let origin = request.url().origin().ascii_serialization();
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire(request.url().as_str()).await;
+        }
+        let start = std::time::Instant::now();
+        let response = self.client.execute(request).await;
+        let outcome = match &response {
+            Ok(response) => check_response(response),
+            Err(e) => Err(e.to_string()),
+        };
+        health::get().record(&origin, outcome, start.elapsed());
+        response
+    }
+}
+
+fn redirect_location(response: &Response) -> Option<reqwest::Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    let location = response.headers().get(header::LOCATION)?.to_str().ok()?;
+    response.url().join(location).ok()
+}
+
+/// Whether a response shows the origin is serving pages. Client errors
+/// other than rate limiting count as healthy, Cloudflare challenges do not.
+fn check_response(response: &Response) -> Result<(), String> {
+    let status = response.status();
+    if response.headers().contains_key("cf-mitigated") {
+        return Err(format!("{status} Cloudflare challenge"));
+    }
+    if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(status.to_string());
+    }
+    Ok(())
+}
+
+pub struct RequestBuilder<'a> {
+    fetcher: &'a Fetcher,
+    inner: reqwest::RequestBuilder,
+}
+
+impl RequestBuilder<'_> {
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        header::HeaderName: TryFrom<K>,
+        <header::HeaderName as TryFrom<K>>::Error: Into<http::Error>,
+        header::HeaderValue: TryFrom<V>,
+        <header::HeaderValue as TryFrom<V>>::Error: Into<http::Error>,
+    {
+        self.inner = self.inner.header(key, value);
+        self
+    }
+
+    pub async fn send(self) -> reqwest::Result<Response> {
+        let request = self.inner.build()?;
+        self.fetcher.execute(request).await
+    }
+}
 
 pub static ON_AIR: LazyLock<Fetcher> = LazyLock::new(|| {
     let config = &config::get().fetcher;
@@ -20,7 +120,9 @@ pub fn get_bangumi() -> &'static Fetcher {
 }
 
 pub fn crate_fetcher(config: &config::fetcher::Fetcher, proxy: Option<String>) -> Fetcher {
-    let mut client_builder = reqwest::ClientBuilder::new();
+    // redirects are followed by `Fetcher::execute` to rate limit every hop
+    let mut client_builder =
+        reqwest::ClientBuilder::new().redirect(reqwest::redirect::Policy::none());
     if config.use_proxy {
         if let Some(proxy) = proxy {
             client_builder = client_builder.proxy(reqwest::Proxy::all(proxy).unwrap());
@@ -45,5 +147,8 @@ pub fn crate_fetcher(config: &config::fetcher::Fetcher, proxy: Option<String>) -
                 .collect(),
         );
     }
-    client_builder.build().expect("Failed to initialize config")
+    Fetcher {
+        client: client_builder.build().expect("Failed to initialize config"),
+        limiter: config.rate_limit.as_ref().map(RateLimiter::new),
+    }
 }
@@ -0,0 +1,90 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket that may go into debt: a request always takes its token
+/// and waits for as long as the bucket is below zero, so waiters are served
+/// in the order they arrived.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(burst: f64, now: Instant) -> Self {
+        Self {
+            tokens: burst,
+            updated: now,
+        }
+    }
+
+    /// Take a token at `now` and return how long to wait before using it.
+    fn reserve(&mut self, now: Instant, per_second: f64, burst: f64) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(burst);
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / per_second)
+        }
+    }
+}
+
+/// Requests per second and burst shared by every request of a client. A
+/// client talks to one backend, so its mirrors share the limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &config::fetcher::RateLimit) -> Self {
+        let burst = config.burst.max(1) as f64;
+        Self {
+            per_second: config.per_second,
+            burst,
+            bucket: Mutex::new(Bucket::new(burst, Instant::now())),
+        }
+    }
+
+    /// Wait until a request to `url` is allowed.
+    pub async fn acquire(&self, url: &str) {
+        if self.per_second <= 0.0 {
+            return;
+        }
+        let wait = {
+            let now = Instant::now();
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.reserve(now, self.per_second, self.burst)
+        };
+        if !wait.is_zero() {
+            tracing::debug!("Rate limited on {}, waiting {:?}", url, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2.0, start);
+        assert_eq!(Duration::ZERO, bucket.reserve(start, 1.0, 2.0));
+        assert_eq!(Duration::ZERO, bucket.reserve(start, 1.0, 2.0));
+        // out of tokens, the next ones queue one second apart
+        assert_eq!(Duration::from_secs(1), bucket.reserve(start, 1.0, 2.0));
+        assert_eq!(Duration::from_secs(2), bucket.reserve(start, 1.0, 2.0));
+        // refilled while idle, never above the burst
+        let later = start + Duration::from_secs(60);
+        assert_eq!(Duration::ZERO, bucket.reserve(later, 1.0, 2.0));
+        assert_eq!(Duration::ZERO, bucket.reserve(later, 1.0, 2.0));
+        assert_eq!(Duration::from_secs(1), bucket.reserve(later, 1.0, 2.0));
+    }
+}
@@ -32,6 +32,11 @@ where
         }
     }
 
+    /// Whether a task for `key` is running or waiting for a permit.
+    pub async fn contains(&self, key: &K) -> bool {
+        self.inner.lock().await.contains_key(key)
+    }
+
     pub async fn get_or_spawn<F, Fut>(&self, key: K, task: F) -> Result<V, Arc<anyhow::Error>>
     where
         F: FnOnce() -> Fut + Send + 'static,
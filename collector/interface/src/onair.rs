@@ -1,19 +1,148 @@
-pub async fn fetch() -> anyhow::Result<(String, String)> {
-    let mirror = &config::get().collector.onair.mirror;
+use fetcher::{StatusCode, header};
+use service::onair::Validators;
+use std::io::{BufReader, Seek};
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// bangumi-data `data.json` in an anonymous temporary file (or the local
+/// mirror itself), with its md5 and the validators to store for next time.
+pub struct Download {
+    pub hash: String,
+    pub file: std::fs::File,
+    pub validators: Option<Validators>,
+}
+
+/// Mirrors that are not URLs are local files, optionally `file://` prefixed.
+fn local_path(mirror: &str) -> Option<&Path> {
+    if mirror.starts_with("http://") || mirror.starts_with("https://") {
+        return None;
+    }
+    Some(Path::new(mirror.strip_prefix("file://").unwrap_or(mirror)))
+}
+
+async fn read_local(path: &Path) -> anyhow::Result<Download> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut context = md5::Context::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        context.consume(&buf[..n]);
+    }
+    let mut file = file.into_std().await;
+    file.rewind()?;
+    let hash = format!("{:x}", context.finalize());
+    Ok(Download {
+        hash,
+        file,
+        validators: None,
+    })
+}
+
+/// Download `mirror`, or `None` when it answers 304 to the stored validators.
+/// The body is hashed on the way to disk and never held in memory.
+async fn download(mirror: &str, cached: Option<&Validators>) -> anyhow::Result<Option<Download>> {
     let fetcher = fetcher::get_onair();
-    let data: String = fetcher.get(mirror).send().await?.text().await?;
-    let hash = md5::compute(&data);
-    let hash = format!("{:x}", hash);
-    Ok((hash, data))
+    let mut request = fetcher.get(mirror);
+    if let Some(cached) = cached.filter(|v| v.mirror == mirror) {
+        if let Some(etag) = &cached.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    let mut response = response.error_for_status()?;
+    let header_of = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = Validators {
+        mirror: mirror.to_string(),
+        etag: header_of(header::ETAG),
+        last_modified: header_of(header::LAST_MODIFIED),
+    };
+    let mut file = tokio::fs::File::from_std(tempfile::tempfile()?);
+    let mut context = md5::Context::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        context.consume(&chunk);
+        file.write_all(&chunk).await?;
+        size += chunk.len();
+    }
+    file.flush().await?;
+    let mut file = file.into_std().await;
+    file.rewind()?;
+    tracing::debug!("OnAir data downloaded from {}, {} bytes", mirror, size);
+    let hash = format!("{:x}", context.finalize());
+    Ok(Some(Download {
+        hash,
+        file,
+        validators: Some(validators),
+    }))
+}
+
+/// Fetch from the first mirror that works, `None` when it is unchanged
+/// since the validators in `cached`.
+pub async fn fetch(cached: Option<&Validators>) -> anyhow::Result<Option<Download>> {
+    let mirrors = &config::get().collector.onair.mirrors;
+    let mut last_error = anyhow::anyhow!("No OnAir mirrors configured");
+    for mirror in mirrors {
+        let result = match local_path(mirror) {
+            Some(path) => read_local(path).await.map(Some),
+            None => download(mirror, cached).await,
+        };
+        match result {
+            Ok(download) => return Ok(download),
+            Err(e) => {
+                tracing::warn!("OnAir mirror {} failed: {:#}", mirror, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
 }
 
 pub async fn refresh() -> anyhow::Result<()> {
-    let (hash, data) = fetch().await?;
-    if !service::onair::diff_hash(&hash).await? {
+    let record = service::onair::record().await?;
+    let Some(Download {
+        hash,
+        file,
+        validators,
+    }) = fetch(record.validators.as_ref()).await?
+    else {
+        tracing::debug!("OnAir data not modified, skip");
+        return Ok(());
+    };
+    // site meta is checked too so that it gets stored once even when the
+    // data has not changed since it started being persisted
+    if !record.diff_hash(&hash) && !service::onair::site_meta().await?.is_empty() {
         tracing::debug!("OnAir data not changed, skip");
+        if validators != record.validators {
+            service::onair::touch(&hash, validators.as_ref()).await?;
+        }
         return Ok(());
     }
-    let items = parser::onair::parse(&data)?;
-    service::onair::flush(hash, items).await?;
+    let batch_size = config::get().collector.onair.batch_size;
+    // keep the parser at most a couple of batches ahead of the database
+    let (tx, rx) = mpsc::channel(2);
+    let parse = tokio::task::spawn_blocking(move || {
+        parser::onair::parse_reader(BufReader::new(file), batch_size, |batch| {
+            tx.blocking_send(batch)
+                .map_err(|_| anyhow::anyhow!("OnAir flush stopped"))
+        })
+    });
+    let site_meta = async move { parse.await? };
+    service::onair::flush(hash, validators, rx, site_meta).await?;
     Ok(())
 }
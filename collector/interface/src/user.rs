@@ -1,13 +1,31 @@
 use crate::common::TaskQueue;
-use std::sync::LazyLock;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
 
 use anyhow::anyhow;
-use chrono::Utc;
-use model::common::user::{InitUser, NamesUpdate, Uid, UserState};
-use model::prelude::User;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use model::common::user::{
+    CollectionPage, InitUser, NamesUpdate, SubjectState, SubjectType, Uid, UserState,
+    names_from_renames,
+};
+use model::prelude::{DateTimeUtc, User, UserCollection, Uuid};
 
-static NAME_QUEUE: LazyLock<TaskQueue<Uid, User>> = LazyLock::new(|| TaskQueue::new(10));
+/// Keyed by uid and whether the run is forced, so a forced refresh never
+/// joins a run that may stop at the fresh duration.
+static NAME_QUEUE: LazyLock<TaskQueue<(Uid, bool), User>> = LazyLock::new(|| TaskQueue::new(10));
 static HOME_QUEUE: LazyLock<TaskQueue<Uid, User>> = LazyLock::new(|| TaskQueue::new(10));
+static FRIEND_QUEUE: LazyLock<TaskQueue<Uuid, User>> = LazyLock::new(|| TaskQueue::new(4));
+static COLLECTION_QUEUE: LazyLock<TaskQueue<(Uuid, SubjectType, SubjectState), ()>> =
+    LazyLock::new(|| TaskQueue::new(4));
+static REFRESH_JOBS: LazyLock<Mutex<HashMap<Uuid, RefreshJob>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// How long a finished refresh job can still be looked up.
+const REFRESH_JOB_RETENTION_HOURS: i64 = 1;
+
+/// Upper bound of pages fetched for one collection list.
+const MAX_COLLECTION_PAGES: usize = 1000;
 
 pub struct Compass {
     uid: Uid,
@@ -19,7 +37,9 @@ impl Compass {
     }
 
     pub fn with_origin(path: &str) -> String {
-        format!("{}/{path}", config::get().collector.user.random_origin()).to_string()
+        let origins = &config::get().collector.user.origins;
+        let origin = fetcher::health::pick_origin(origins).expect("No origins available");
+        format!("{origin}/{path}")
     }
 
     pub fn home(&self) -> String {
@@ -32,23 +52,70 @@ impl Compass {
             self.uid.to_string()
         ))
     }
+
+    pub fn friends(&self) -> String {
+        Self::with_origin(&format!("user/{}/friends", self.uid.to_string()))
+    }
+
+    pub fn collection_list(
+        &self,
+        subject_type: SubjectType,
+        state: SubjectState,
+        page: usize,
+    ) -> String {
+        Self::with_origin(&format!(
+            "{subject_type}/list/{}/{state}?page={page}",
+            self.uid.to_string()
+        ))
+    }
+}
+
+/// The uid in a user page url, e.g. `sai` in `https://bgm.tv/user/sai/timeline`.
+fn uid_in_url(url: &str) -> Option<Uid> {
+    let segment = url.split('/').nth(4)?;
+    let segment = segment.split(['?', '#']).next()?;
+    (!segment.is_empty()).then(|| Uid::from_str(segment))
+}
+
+/// Carry the row of a user looked up by a former sid over to `sid`, which
+/// the user page redirected to.
+async fn follow_sid_change(uid: &Uid, sid: &str) -> anyhow::Result<Option<User>> {
+    let Uid::Sid(old) = uid else {
+        return Ok(None);
+    };
+    if old == sid {
+        return Ok(None);
+    }
+    let Some(user) = service::user::find_by_uid(uid.clone()).await? else {
+        return Ok(None);
+    };
+    tracing::info!("User {} renamed sid {} to {}", user.id, old, sid);
+    service::user::rename_sid(user, sid.to_string())
+        .await
+        .map(Some)
 }
 
 async fn fetch_user_info(uid: Uid) -> anyhow::Result<InitUser> {
     let url = Compass::new(uid.clone()).home();
     let fetcher = fetcher::get_bangumi();
-    let html = fetcher.get(url).send().await?.text().await?;
+    let ret = fetcher.get(url).send().await?;
+    // a former sid or a nid redirects to the current sid
+    let uid = uid_in_url(ret.url().as_str()).unwrap_or(uid);
+    let source = ret.url().to_string();
+    let html = ret.text().await?;
     let mut init = InitUser::default();
-    init.update_uid(uid);
+    init.update_uid(uid).set_source(Some(source));
     parser::user::parse_userpage(&html, Some(init))
 }
 
+/// Name changes newer than `key_point`, and the uid the timeline was found
+/// under after following redirects.
 async fn fetch_names_update_until_key_point(
     uid: Uid,
     key_point: chrono::DateTime<chrono::Utc>,
-) -> anyhow::Result<Option<NamesUpdate>> {
+) -> anyhow::Result<(Option<NamesUpdate>, Uid)> {
     let mut page = 1;
-    let mut all_names = std::collections::HashSet::new();
+    let mut renames = Vec::new();
     let mut kp = None;
     let mut compass = Compass::new(uid.clone());
     loop {
@@ -60,8 +127,10 @@ async fn fetch_names_update_until_key_point(
         tracing::debug!("Final URL: {}", final_url);
         if !final_url.eq(&url) {
             tracing::warn!("Redirected to {}, try fetching with new UID", final_url);
-            let sid = final_url.split('/').nth(4).unwrap_or("");
-            compass.uid = Uid::from_str(sid);
+            let Some(uid) = uid_in_url(&final_url) else {
+                anyhow::bail!("Redirected to {final_url} without a user");
+            };
+            compass.uid = uid;
             continue;
         }
         if !ret.status().is_success() {
@@ -85,7 +154,7 @@ async fn fetch_names_update_until_key_point(
             if kp.is_none() {
                 kp = Some(name_history.key_point.clone());
             }
-            all_names.extend(name_history.names);
+            renames.extend(name_history.renames);
             if key_point > name_history.key_point {
                 break;
             }
@@ -99,9 +168,9 @@ async fn fetch_names_update_until_key_point(
     }
     let names_update = kp.map(|key_point| NamesUpdate {
         key_point,
-        names: all_names,
+        names: names_from_renames(renames),
     });
-    Ok(names_update)
+    Ok((names_update, compass.uid))
 }
 
 async fn update_user_data(uid: Uid) -> anyhow::Result<User> {
@@ -110,6 +179,9 @@ async fn update_user_data(uid: Uid) -> anyhow::Result<User> {
     let task = async move || {
         let user = fetch_user_info(uid.clone()).await?;
         tracing::debug!("Fetched user info: {:?}", user);
+        if let Some(sid) = &user.sid {
+            follow_sid_change(&uid, sid).await?;
+        }
         service::user::upsert_user(user).await
     };
     queue
@@ -118,12 +190,12 @@ async fn update_user_data(uid: Uid) -> anyhow::Result<User> {
         .map_err(|err| anyhow!("Failed to update user data: {:?}", err))
 }
 
-async fn update_name_history(uid: Uid, user: User) -> anyhow::Result<User> {
-    let key = uid.clone();
+async fn update_name_history(uid: Uid, user: User, force: bool) -> anyhow::Result<User> {
+    let key = (uid.clone(), force);
     let queue = &NAME_QUEUE;
-    let task = || async move {
+    let task = move || async move {
         let key_point = if let Some(name_history) = &user.extra.name_history {
-            if !is_expired(name_history.update_at, &user.state) {
+            if !force && !is_expired(name_history.update_at, &user.state) {
                 return Ok(user);
             }
             name_history.key_point
@@ -131,14 +203,24 @@ async fn update_name_history(uid: Uid, user: User) -> anyhow::Result<User> {
             chrono::DateTime::<chrono::Utc>::MIN_UTC
         };
 
-        let sid = user.sid.clone();
-        let nid = user.nid.clone();
-        let uid = sid.map_or_else(|| Uid::Nid(nid.unwrap()), |sid| Uid::Sid(sid));
+        let uid = uid_of(&user);
         let names_update = fetch_names_update_until_key_point(uid.clone(), key_point).await;
-        let Ok(names_update) = names_update else {
-            tracing::error!("Failed to fetch names update: {:?}", names_update);
-            return Ok(user);
+        let (names_update, current) = match names_update {
+            Ok(names_update) => names_update,
+            Err(e) if force => return Err(e.context("Failed to fetch names update")),
+            Err(e) => {
+                tracing::error!("Failed to fetch names update: {:?}", e);
+                return Ok(user);
+            }
+        };
+        let user = match current {
+            Uid::Sid(sid) if user.sid.as_ref() != Some(&sid) => {
+                tracing::info!("User {} redirected to sid {}", user.id, sid);
+                service::user::rename_sid(user, sid).await?
+            }
+            _ => user,
         };
+        let uid = uid_of(&user);
         tracing::debug!("Fetched names update: {:?}", names_update);
         let Some(names_update) = names_update else {
             tracing::debug!("No names update found: {:?}", names_update);
@@ -163,7 +245,31 @@ async fn update_user_data_if_expired(uid: Uid, user: User) -> anyhow::Result<Use
             return Ok(user);
         }
     }
-    update_name_history(uid, user).await
+    update_name_history(uid, user, false).await
+}
+
+fn spawn_refresh_if_expired(uid: Uid, user: User) {
+    tokio::spawn(async move {
+        if let Err(e) = update_user_data_if_expired(uid.clone(), user).await {
+            tracing::warn!("Failed to refresh user {}: {:?}", uid.to_string(), e);
+        }
+    });
+}
+
+/// Whether the profile or the name history of `user` is due for a refresh.
+fn needs_refresh(user: &User) -> bool {
+    is_expired(user.update_at, &user.state)
+        || user
+            .extra
+            .name_history
+            .as_ref()
+            .is_none_or(|name_history| is_expired(name_history.update_at, &user.state))
+}
+
+fn uid_of(user: &User) -> Uid {
+    let sid = user.sid.clone();
+    let nid = user.nid.clone();
+    sid.map_or_else(|| Uid::Nid(nid.unwrap()), |sid| Uid::Sid(sid))
 }
 
 fn get_fresh_duration(state: &UserState) -> chrono::Duration {
@@ -188,6 +294,434 @@ pub async fn query_user(uid: Uid) -> anyhow::Result<User> {
         update_user_data(uid.clone()).await?
     };
     let result = user.clone();
-    tokio::spawn(async move { update_user_data_if_expired(uid, user).await });
+    spawn_refresh_if_expired(uid, user);
     Ok(result)
 }
+
+/// Outcome of a cached lookup in [`query_users`].
+pub enum Lookup {
+    /// Cached and within the fresh duration of its state.
+    Fresh(User),
+    /// Cached but expired; a refresh has been scheduled.
+    Stale(User),
+    /// A fetch for this uid was already running, the cached user if any.
+    Queued(Option<User>),
+    /// Never looked up; nothing is scheduled.
+    NotFound,
+}
+
+async fn is_queued(uid: &Uid) -> bool {
+    HOME_QUEUE.contains(uid).await
+        || NAME_QUEUE.contains(&(uid.clone(), false)).await
+        || NAME_QUEUE.contains(&(uid.clone(), true)).await
+}
+
+/// Look up many users from the cache in one query without waiting for any
+/// fetch. Expired users are refreshed in the background through the same
+/// queues as [`query_user`]. Results follow the order of `uids`.
+pub async fn query_users(uids: Vec<Uid>) -> anyhow::Result<Vec<(Uid, Lookup)>> {
+    let users = service::user::find_by_uids(&uids).await?;
+    let mut result = Vec::with_capacity(uids.len());
+    for uid in uids {
+        let user = users
+            .iter()
+            .find(|(found, _)| *found == uid)
+            .map(|(_, user)| user.clone());
+        // a former sid is refreshed under the current one
+        let key = user.as_ref().map_or_else(|| uid.clone(), uid_of);
+        let queued = is_queued(&uid).await || is_queued(&key).await;
+        let lookup = match user {
+            _ if queued => Lookup::Queued(user),
+            None => Lookup::NotFound,
+            Some(user) if !needs_refresh(&user) => Lookup::Fresh(user),
+            Some(user) => {
+                spawn_refresh_if_expired(key, user.clone());
+                Lookup::Stale(user)
+            }
+        };
+        result.push((uid, lookup));
+    }
+    Ok(result)
+}
+
+/// Refresh every user whose data is older than the fresh duration of its
+/// state, `collector.user.refresh_batch_size` at a time through the same
+/// queues as lookups, then runs [`refresh_friends`] and [`discover_users`].
+/// Returns how many users were refreshed.
+pub async fn refresh_expired() -> anyhow::Result<usize> {
+    let now = Utc::now();
+    let cutoffs = [
+        UserState::Active,
+        UserState::Abondon,
+        UserState::Dropped,
+        UserState::Banned,
+    ]
+    .map(|state| {
+        let cutoff = now - get_fresh_duration(&state);
+        (state, cutoff)
+    });
+    let batch_size = config::get().collector.user.refresh_batch_size.max(1);
+    let mut cursor = None;
+    let (mut refreshed, mut failed) = (0, 0);
+    loop {
+        let users = service::user::find_expired(&cutoffs, cursor, batch_size).await?;
+        let Some(last) = users.last() else {
+            break;
+        };
+        cursor = Some((last.update_at, last.id));
+        let tasks = users.into_iter().map(|user| {
+            let uid = uid_of(&user);
+            async move { (uid.clone(), update_user_data_if_expired(uid, user).await) }
+        });
+        for (uid, result) in join_all(tasks).await {
+            match result {
+                Ok(_) => refreshed += 1,
+                Err(e) => {
+                    tracing::warn!("Failed to refresh user {}: {:?}", uid.to_string(), e);
+                    failed += 1;
+                }
+            }
+        }
+        tracing::info!("User refresh: {} refreshed, {} failed", refreshed, failed);
+    }
+    refresh_friends().await?;
+    let discovered = discover_users().await?;
+    if discovered > 0 {
+        tracing::info!("User refresh: {} discovered through friends", discovered);
+    }
+    Ok(refreshed)
+}
+
+#[derive(Debug, Clone)]
+pub enum RefreshState {
+    Running,
+    /// Whether the profile and the name history changed, and the user after
+    /// the refresh.
+    Done {
+        profile: bool,
+        name_history: bool,
+        user: Box<User>,
+    },
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct RefreshJob {
+    pub id: Uuid,
+    pub uid: Uid,
+    pub state: RefreshState,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Scrape `uid` again regardless of its fresh duration and return the job
+/// to poll with [`refresh_status`]. A refresh already running for the same
+/// uid is returned instead of starting another one.
+pub fn refresh_user(uid: Uid) -> RefreshJob {
+    let now = Utc::now();
+    let retention = chrono::Duration::hours(REFRESH_JOB_RETENTION_HOURS);
+    let mut jobs = REFRESH_JOBS.lock().unwrap();
+    jobs.retain(|_, job| job.finished_at.is_none_or(|at| now - at < retention));
+    if let Some(job) = jobs
+        .values()
+        .find(|job| job.uid == uid && job.finished_at.is_none())
+    {
+        return job.clone();
+    }
+    let job = RefreshJob {
+        id: Uuid::new_v4(),
+        uid: uid.clone(),
+        state: RefreshState::Running,
+        started_at: now,
+        finished_at: None,
+    };
+    jobs.insert(job.id, job.clone());
+    drop(jobs);
+
+    let id = job.id;
+    tokio::spawn(async move {
+        let state = match force_refresh(uid.clone()).await {
+            Ok((user, profile, name_history)) => RefreshState::Done {
+                profile,
+                name_history,
+                user: Box::new(user),
+            },
+            Err(e) => {
+                tracing::warn!("Failed to refresh user {}: {:?}", uid.to_string(), e);
+                RefreshState::Failed(format!("{e:#}"))
+            }
+        };
+        if let Some(job) = REFRESH_JOBS.lock().unwrap().get_mut(&id) {
+            job.state = state;
+            job.finished_at = Some(Utc::now());
+        }
+    });
+    job
+}
+
+pub fn refresh_status(id: Uuid) -> Option<RefreshJob> {
+    REFRESH_JOBS.lock().unwrap().get(&id).cloned()
+}
+
+/// Whether anything scraped from the home page differs between `before` and
+/// `after`.
+fn profile_changed(before: &User, after: &User) -> bool {
+    let profile_of = |user: &User| {
+        (
+            user.name.clone(),
+            user.nid,
+            user.sid.clone(),
+            user.state.clone(),
+            user.join_time,
+            user.last_active,
+            user.extra.collections.clone(),
+            user.extra.profile.clone(),
+        )
+    };
+    profile_of(before) != profile_of(after)
+}
+
+async fn force_refresh(uid: Uid) -> anyhow::Result<(User, bool, bool)> {
+    let before = service::user::find_by_uid(uid.clone()).await?;
+    let user = update_user_data(uid.clone()).await?;
+    let profile = before
+        .as_ref()
+        .is_none_or(|before| profile_changed(before, &user));
+    let names_before = before
+        .and_then(|before| before.extra.name_history)
+        .map(|name_history| name_history.names);
+    let user = update_name_history(uid, user, true).await?;
+    let names_after = user.extra.name_history.as_ref().map(|h| &h.names);
+    let name_history = names_after != names_before.as_ref();
+    Ok((user, profile, name_history))
+}
+
+/// Every page of one collection list; `has_next` is set when the list was
+/// cut off at `MAX_COLLECTION_PAGES`.
+async fn fetch_collection_list(
+    uid: Uid,
+    subject_type: SubjectType,
+    state: SubjectState,
+) -> anyhow::Result<CollectionPage> {
+    let compass = Compass::new(uid);
+    let mut items = Vec::new();
+    for page in 1..=MAX_COLLECTION_PAGES {
+        let url = compass.collection_list(subject_type, state, page);
+        tracing::debug!("Fetching collection page {}: {}", page, url);
+        let fetcher = fetcher::get_bangumi();
+        let ret = fetcher.get(&url).send().await?;
+        if !ret.status().is_success() {
+            anyhow::bail!("Failed to fetch collection page {url}: {}", ret.status());
+        }
+        let html = ret.text().await?;
+        let list = parser::user::parse_collection_list(&html)?;
+        let last = !list.has_next || list.items.is_empty();
+        items.extend(list.items);
+        if last {
+            return Ok(CollectionPage {
+                items,
+                has_next: false,
+            });
+        }
+    }
+    tracing::warn!(
+        "Collection list {}/{} of {} stopped at {} pages",
+        subject_type,
+        state,
+        compass.uid.to_string(),
+        MAX_COLLECTION_PAGES
+    );
+    Ok(CollectionPage {
+        items,
+        has_next: true,
+    })
+}
+
+/// Fetch every page of one collection list of `user` and replace the stored
+/// list with it. A list cut off at the page cap only adds and updates items.
+async fn sync_collection_list(
+    user: User,
+    subject_type: SubjectType,
+    state: SubjectState,
+) -> anyhow::Result<()> {
+    let key = (user.id, subject_type, state);
+    let task = move || async move {
+        let at = Utc::now();
+        let list = fetch_collection_list(uid_of(&user), subject_type, state).await?;
+        tracing::debug!(
+            "Fetched {} items of {}/{} for user {}",
+            list.items.len(),
+            subject_type,
+            state,
+            user.id
+        );
+        let complete = !list.has_next;
+        service::user::replace_collection_list(&user, subject_type, state, list.items, complete, at)
+            .await
+    };
+    COLLECTION_QUEUE
+        .get_or_spawn(key, task)
+        .await
+        .map_err(|err| anyhow!("Failed to sync collection list: {:?}", err))
+}
+
+/// Collection lists of a user with the oldest sync time among them, `None`
+/// while one of them has never been synced.
+pub struct CollectionList {
+    pub user: User,
+    pub items: Vec<UserCollection>,
+    pub synced_at: Option<DateTimeUtc>,
+}
+
+/// Stored collection lists of `uid`. Lists never synced or expired are
+/// synced in the background, as a heavy user can take hundreds of pages.
+/// All states are returned when `state` is `None`.
+pub async fn query_collection_list(
+    uid: Uid,
+    subject_type: SubjectType,
+    state: Option<SubjectState>,
+) -> anyhow::Result<CollectionList> {
+    let user = query_user(uid).await?;
+    let states = state.map_or(SubjectState::ALL.to_vec(), |state| vec![state]);
+    let mut synced_at = Some(DateTimeUtc::MAX_UTC);
+    for state in states {
+        let at = service::user::collection_list_synced_at(&user, subject_type, state).await?;
+        if at.is_none_or(|at| is_expired(at, &user.state)) {
+            let user = user.clone();
+            tokio::spawn(async move {
+                if let Err(e) = sync_collection_list(user, subject_type, state).await {
+                    tracing::warn!("Failed to refresh collection list: {:?}", e);
+                }
+            });
+        }
+        synced_at = synced_at.zip(at).map(|(oldest, at)| oldest.min(at));
+    }
+    let items = service::user::find_collection_list(&user, subject_type, state).await?;
+    Ok(CollectionList {
+        user,
+        items,
+        synced_at,
+    })
+}
+
+fn friends_cutoff() -> DateTime<Utc> {
+    let days = config::get().collector.user.friends_fresh_duration;
+    Utc::now() - chrono::Duration::days(days)
+}
+
+fn friends_expired(user: &User) -> bool {
+    user.extra
+        .friends
+        .as_ref()
+        .is_none_or(|friends| friends.update_at < friends_cutoff())
+}
+
+/// Scrape the stalest friends pages, `collector.user.friends_batch_size`
+/// per call, on their own cadence so they do not follow every profile
+/// refresh. Returns how many were stored.
+pub async fn refresh_friends() -> anyhow::Result<usize> {
+    let limit = config::get().collector.user.friends_batch_size;
+    if limit == 0 {
+        return Ok(0);
+    }
+    let users = service::user::find_friends_expired(friends_cutoff(), limit).await?;
+    let tasks = users.iter().map(|user| async move {
+        let result = update_friends(user).await;
+        if let Err(e) = &result {
+            tracing::warn!("Failed to refresh friends of user {}: {:?}", user.id, e);
+        }
+        result.is_ok()
+    });
+    let refreshed = join_all(tasks).await.into_iter().filter(|ok| *ok).count();
+    tracing::info!("User refresh: {} friends pages refreshed", refreshed);
+    Ok(refreshed)
+}
+
+/// Fetch the friends page of `user` and store it.
+async fn update_friends(user: &User) -> anyhow::Result<User> {
+    let (id, uid) = (user.id, uid_of(user));
+    let task = move || async move {
+        let at = Utc::now();
+        let url = Compass::new(uid).friends();
+        let fetcher = fetcher::get_bangumi();
+        let ret = fetcher.get(&url).send().await?;
+        if !ret.status().is_success() {
+            anyhow::bail!("Failed to fetch friends page {url}: {}", ret.status());
+        }
+        let html = ret.text().await?;
+        let friends = parser::user::parse_friends(&html)?;
+        tracing::debug!("Fetched {} friends for user {}", friends.len(), id);
+        service::user::update_friends(id, friends, at).await
+    };
+    FRIEND_QUEUE
+        .get_or_spawn(id, task)
+        .await
+        .map_err(|err| anyhow!("Failed to update friends: {:?}", err))
+}
+
+/// `uid` with its friends stored. Friends never fetched are fetched before
+/// returning; expired ones are refreshed in the background.
+pub async fn query_friends(uid: Uid) -> anyhow::Result<User> {
+    let user = query_user(uid).await?;
+    if user.extra.friends.is_none() {
+        return update_friends(&user).await;
+    }
+    if friends_expired(&user) {
+        let user = user.clone();
+        tokio::spawn(async move {
+            if let Err(e) = update_friends(&user).await {
+                tracing::warn!("Failed to refresh friends of user {}: {:?}", user.id, e);
+            }
+        });
+    }
+    Ok(user)
+}
+
+/// Start tracking users that so far only appear on friends pages,
+/// `collector.user.discover_batch_size` per call. Returns how many were
+/// fetched.
+pub async fn discover_users() -> anyhow::Result<usize> {
+    let limit = config::get().collector.user.discover_batch_size;
+    if limit == 0 {
+        return Ok(0);
+    }
+    let uids = service::user::find_undiscovered(limit).await?;
+    let tasks = uids.into_iter().map(|uid| async move {
+        let result = update_user_data(uid.clone()).await;
+        if let Err(e) = &result {
+            tracing::warn!("Failed to discover user {}: {:?}", uid.to_string(), e);
+        }
+        result.is_ok()
+    });
+    let discovered = join_all(tasks).await.into_iter().filter(|ok| *ok).count();
+    Ok(discovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::prelude::{Extra, Profile};
+
+    #[test]
+    fn test_profile_changed() {
+        let before = User {
+            id: Uuid::nil(),
+            nid: Some(1),
+            sid: Some("sai".to_string()),
+            name: "Sai".to_string(),
+            state: UserState::Active,
+            join_time: None,
+            last_active: None,
+            update_at: Utc::now(),
+            extra: Extra::default(),
+        };
+        let mut after = before.clone();
+        after.update_at = Utc::now() + chrono::Duration::days(1);
+        assert!(!profile_changed(&before, &after));
+        after.extra.update_profile(Profile {
+            sign: Some("hello".to_string()),
+            ..Default::default()
+        });
+        assert!(profile_changed(&before, &after));
+    }
+}
@@ -1,7 +1,180 @@
-use model::common::onair::{BangumiData, BangumiItemMap};
-use std::str::FromStr;
+use model::common::onair::{BangumiItem, BangumiItemMap, SiteMetaMap, SubjectId};
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+use std::io::Read;
 
-pub fn parse(data: &str) -> anyhow::Result<BangumiItemMap> {
-    let data = BangumiItemMap::from(BangumiData::from_str(data)?);
-    Ok(data)
+/// Stream bangumi-data `data.json` from `reader` without holding every item
+/// in memory. Items are handed to `on_batch` up to `batch_size` at a time,
+/// and `siteMeta` is returned once the whole document is read.
+pub fn parse_reader<R, F>(reader: R, batch_size: usize, on_batch: F) -> anyhow::Result<SiteMetaMap>
+where
+    R: Read,
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    let mut batches = Batches {
+        size: batch_size.max(1),
+        batch: BangumiItemMap::new(),
+        on_batch,
+    };
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let site_meta = DataSeed(&mut batches).deserialize(&mut de)?;
+    de.end()?;
+    Ok(site_meta)
+}
+
+struct Batches<F> {
+    size: usize,
+    batch: BangumiItemMap,
+    on_batch: F,
+}
+
+impl<F> Batches<F>
+where
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    fn push(&mut self, id: SubjectId, item: BangumiItem) -> anyhow::Result<()> {
+        self.batch.insert(id, item);
+        if self.batch.len() >= self.size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        (self.on_batch)(std::mem::take(&mut self.batch))
+    }
+}
+
+/// The top-level object, returning `siteMeta` and streaming `items`.
+struct DataSeed<'a, F>(&'a mut Batches<F>);
+
+impl<'de, F> DeserializeSeed<'de> for DataSeed<'_, F>
+where
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    type Value = SiteMetaMap;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for DataSeed<'_, F>
+where
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    type Value = SiteMetaMap;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bangumi-data object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut site_meta = None;
+        let mut items = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "siteMeta" => site_meta = Some(map.next_value()?),
+                "items" => {
+                    map.next_value_seed(ItemsSeed(&mut *self.0))?;
+                    items = true;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !items {
+            return Err(de::Error::missing_field("items"));
+        }
+        site_meta.ok_or_else(|| de::Error::missing_field("siteMeta"))
+    }
+}
+
+/// The `items` array, flushing the last partial batch at its end.
+struct ItemsSeed<'a, F>(&'a mut Batches<F>);
+
+impl<'de, F> DeserializeSeed<'de> for ItemsSeed<'_, F>
+where
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, F> Visitor<'de> for ItemsSeed<'_, F>
+where
+    F: FnMut(BangumiItemMap) -> anyhow::Result<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("bangumi-data items")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(item) = seq.next_element::<BangumiItem>()? {
+            if let Some(id) = item.subject_id() {
+                self.0.push(id, item).map_err(de::Error::custom)?;
+            }
+        }
+        self.0.flush().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use model::common::onair::Site;
+
+    fn item(title: &str, subject: Option<&str>) -> String {
+        let sites = match subject {
+            Some(id) => format!(r#"[{{ "site": "bangumi", "id": "{id}" }}]"#),
+            None => "[]".to_string(),
+        };
+        format!(
+            r#"{{ "title": "{title}", "titleTranslate": {{}}, "type": "tv", "lang": "ja",
+            "officialSite": "", "begin": "", "end": "", "sites": {sites} }}"#
+        )
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let items = [
+            item("a", Some("1")),
+            item("b", None),
+            item("c", Some("3")),
+            item("d", Some("4")),
+        ];
+        let data = format!(
+            r#"{{ "siteMeta": {{ "bangumi": {{ "title": "Bangumi", "urlTemplate": "https://bgm.tv/subject/{{{{id}}}}", "type": "info" }} }},
+            "items": [{}] }}"#,
+            items.join(",")
+        );
+        let mut batches = Vec::new();
+        let site_meta = super::parse_reader(data.as_bytes(), 2, |batch| {
+            let mut ids = batch.into_keys().collect::<Vec<_>>();
+            ids.sort();
+            batches.push(ids);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(vec![vec![1, 3], vec![4]], batches);
+        assert_eq!(
+            "https://bgm.tv/subject/1",
+            site_meta[&Site::Bangumi].render_url("1")
+        );
+
+        let failed = super::parse_reader(data.as_bytes(), 2, |_| anyhow::bail!("stopped"));
+        assert!(failed.is_err());
+        assert!(super::parse_reader(r#"{ "siteMeta": {} }"#.as_bytes(), 2, |_| Ok(())).is_err());
+    }
 }
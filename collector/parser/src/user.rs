@@ -1,4 +1,3 @@
-use std::collections::HashSet;
 use std::vec;
 
 use chrono::{Months, Utc};
@@ -7,8 +6,11 @@ use visdom::types::Elements;
 
 use super::common;
 use model::{
-    common::user::NamesUpdate,
-    prelude::{Collections, InitUser, SubjectType, TypedCollection, Uid, UserState},
+    common::user::{Rename, TimelineRenames},
+    prelude::{
+        CollectionItem, CollectionPage, Collections, Friend, InitUser, NetworkService, Profile,
+        SubjectTags, SubjectType, TypedCollection, Uid, UserState,
+    },
 };
 
 pub fn parse_collection(section: &Elements) -> Option<TypedCollection> {
@@ -29,6 +31,66 @@ pub fn parse_collection(section: &Elements) -> Option<TypedCollection> {
     TypedCollection::build(list)
 }
 
+/// Url in a `background-image:url('...')` style, made absolute.
+fn parse_avatar_url(style: &str) -> Option<String> {
+    let start = style.find("url(")? + "url(".len();
+    let end = start + style[start..].find(')')?;
+    let url = style[start..end].trim_matches(['\'', '"', ' ']);
+    if url.is_empty() {
+        return None;
+    }
+    if url.starts_with("//") {
+        return Some(format!("https:{url}"));
+    }
+    Some(url.to_string())
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+pub fn parse_profile(document: &Elements) -> Profile {
+    let header = document.find("#headerProfile .nameSingle");
+    let avatar = header
+        .find(".headerAvatar .avatar span.avatarNeue")
+        .attr("style")
+        .and_then(|style| parse_avatar_url(&style.to_string()));
+    let sign = non_empty(header.find(".inner .sign").text());
+    let bio = non_empty(document.find("#user_home .bio").text());
+    let mut network_services = Vec::new();
+    document
+        .find("#user_home ul.network_service > li")
+        .for_each(|_, e| {
+            let li = Vis::dom(e);
+            let service = li.find("span.service").text().trim().to_string();
+            let link = li.find("a");
+            let value = if link.length() > 0 {
+                link.text()
+            } else {
+                li.find("span.tip").text()
+            };
+            let value = value.trim().to_string();
+            // the first entry is the join time on Bangumi itself
+            if service.is_empty() || value.is_empty() || value.ends_with(" 加入") {
+                return true;
+            }
+            let url = link.attr("href").map(|href| href.to_string());
+            network_services.push(NetworkService {
+                service,
+                value,
+                url,
+            });
+            true
+        });
+    Profile {
+        avatar,
+        sign,
+        bio,
+        network_services,
+    }
+}
+
 pub fn parse_userpage(html: &str, init: Option<InitUser>) -> anyhow::Result<InitUser> {
     let document = Vis::load(html).map_err(|e| anyhow::anyhow!("Failed to load HTML: {}", e))?;
     let message = document.find(".message>h2").text();
@@ -82,6 +144,7 @@ pub fn parse_userpage(html: &str, init: Option<InitUser>) -> anyhow::Result<Init
 
     let name = name_element.find("a").text();
     init.update_name(name.clone());
+    init.set_profile(Some(parse_profile(&document)));
     let timeline = document
         .find("#pinnedLayout ul.timeline > li small.time")
         .map(|_, element| element.text());
@@ -124,7 +187,86 @@ pub fn parse_userpage(html: &str, init: Option<InitUser>) -> anyhow::Result<Init
     Ok(init.to_owned())
 }
 
-pub fn parse_timeline_name_history(html: &str) -> anyhow::Result<Option<NamesUpdate>> {
+/// One page of `/{type}/list/{uid}/{state}`.
+pub fn parse_collection_list(html: &str) -> anyhow::Result<CollectionPage> {
+    let document = Vis::load(html).map_err(|e| anyhow::anyhow!("Failed to load HTML: {}", e))?;
+    let mut items = Vec::new();
+    document
+        .find("#browserItemList > li.item")
+        .for_each(|_, e| {
+            let li = Vis::dom(e);
+            let subject_id = li
+                .attr("id")
+                .and_then(|id| id.to_string().strip_prefix("item_")?.parse().ok());
+            let Some(subject_id) = subject_id else {
+                return true;
+            };
+            let title = li.find(".inner h3 a").text().trim().to_string();
+            let subtitle = non_empty(li.find(".inner h3 small.grey").text());
+            let info = li.find(".inner .collectInfo");
+            let marked_at = common::parse_time(info.find("span.tip_j").text().trim()).ok();
+            // `starlight stars8`
+            let rating = info.find("span.starlight").attr("class").and_then(|class| {
+                class
+                    .to_string()
+                    .split_whitespace()
+                    .find_map(|c| c.strip_prefix("stars")?.parse().ok())
+            });
+            let tags = info
+                .find("span.tip")
+                .text()
+                .trim()
+                .strip_prefix("标签:")
+                .map(|tags| tags.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default();
+            let comment = non_empty(li.find("#comment_box .text").text());
+            items.push(CollectionItem {
+                subject_id,
+                title,
+                subtitle,
+                rating,
+                tags: SubjectTags(tags),
+                comment,
+                marked_at,
+            });
+            true
+        });
+    let has_next = document
+        .find("#multipage .page_inner a.p")
+        .map(|_, e| e.text())
+        .iter()
+        .any(|text| text.trim() == "››");
+    Ok(CollectionPage { items, has_next })
+}
+
+/// `/user/{uid}/friends`, which lists every friend on one page.
+pub fn parse_friends(html: &str) -> anyhow::Result<Vec<Friend>> {
+    let document = Vis::load(html).map_err(|e| anyhow::anyhow!("Failed to load HTML: {}", e))?;
+    let message = document.find(".message>h2").text();
+    if message.eq("呜咕，出错了") {
+        return Err(anyhow::anyhow!("User not found"));
+    }
+    let mut friends = Vec::new();
+    document.find("#memberUserList li.user").for_each(|_, e| {
+        let link = Vis::dom(e).find("strong a");
+        // `/user/sai`
+        let uid = link.attr("href").and_then(|href| {
+            let href = href.to_string();
+            let uid = href.trim_end_matches('/').rsplit('/').next()?.to_string();
+            (!uid.is_empty()).then(|| Uid::from_str(&uid))
+        });
+        if let Some(uid) = uid {
+            friends.push(Friend {
+                uid,
+                name: link.text().trim().to_string(),
+            });
+        }
+        true
+    });
+    Ok(friends)
+}
+
+pub fn parse_timeline_name_history(html: &str) -> anyhow::Result<Option<TimelineRenames>> {
     let document = Vis::load(html).map_err(|e| anyhow::anyhow!("Failed to load HTML: {}", e))?;
     let timeline = document.find("#timeline");
     if timeline.length() < 1 {
@@ -132,13 +274,27 @@ pub fn parse_timeline_name_history(html: &str) -> anyhow::Result<Option<NamesUpd
     }
     let key_point = timeline.find("h4.Header").first().text();
     let key_point = common::parse_time(&key_point)?;
-    let names = timeline
-        .find("li.tml_item > span > p.status:has(strong) > strong")
-        .map(|_, e| e.text())
-        .into_iter()
-        .collect::<HashSet<String>>();
+    // day headers and their item lists alternate, newest first
+    let mut renames = Vec::new();
+    let mut day = None;
+    timeline.children("h4.Header, ul").for_each(|_, e| {
+        let section = Vis::dom(e);
+        if section.is("h4") {
+            day = common::parse_time(&section.text()).ok();
+            return true;
+        }
+        renames.extend(
+            section
+                .find("li.tml_item > span > p.status:has(strong) > strong")
+                .map(|_, e| Rename {
+                    name: e.text(),
+                    day,
+                }),
+        );
+        true
+    });
 
-    Ok(Some(NamesUpdate { key_point, names }))
+    Ok(Some(TimelineRenames { key_point, renames }))
 }
 
 #[cfg(test)]
@@ -191,6 +347,127 @@ mod tests {
         // println!("{:?}", result);
     }
 
+    #[test]
+    fn test_parse_timeline_names() {
+        let item = |name: &str| {
+            format!(
+                r#"<li class="clearit tml_item"><span class="info"><p class="status">改名为 <strong>{name}</strong></p></span></li>"#
+            )
+        };
+        let html = format!(
+            r#"<div id="timeline">
+            <h4 class="Header">2025-10-02</h4><ul>{}{}</ul>
+            <h4 class="Header">2024-01-05</h4><ul>{}</ul>
+            </div>"#,
+            item("Sai🖖"),
+            item("Sai😊"),
+            item("Sai😊"),
+        );
+        let update = super::parse_timeline_name_history(&html).unwrap().unwrap();
+        let day = |s: &str| crate::common::parse_time(s).ok();
+        assert_eq!(day("2025-10-02").unwrap(), update.key_point);
+        let renames = update
+            .renames
+            .iter()
+            .map(|r| (r.name.as_str(), r.day))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("Sai🖖", day("2025-10-02")),
+                ("Sai😊", day("2025-10-02")),
+                ("Sai😊", day("2024-01-05")),
+            ],
+            renames
+        );
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let html = r#"<div id="headerProfile"><h1 class="nameSingle">
+            <div class="headerAvatar"><a href="/user/sai" class="avatar"><span class="avatarNeue avatarSize75" style="background-image:url('//lain.bgm.tv/pic/user/l/000/00/00/1.jpg?r=1700000000')"></span></a></div>
+            <div class="inner"><div class="name"><a href="/user/sai">Sai🖖</a> <small class="grey">@sai</small></div><span class="sign"> Bangumi 番组计划 </span></div>
+            </h1></div>
+            <div id="user_home">
+            <div class="bio">Hello</div>
+            <ul class="network_service">
+            <li><span class="service" style="background-color:#F09199">Bangumi</span><span class="tip">2008-7-14 加入</span></li>
+            <li><span class="service" style="background-color:#1DA1F2">Twitter</span><a href="https://twitter.com/sai" class="l" target="_blank">sai</a></li>
+            <li><span class="service" style="background-color:#000">PSN</span><span class="tip">sai_psn</span></li>
+            </ul></div>"#;
+        let document = visdom::Vis::load(html).unwrap();
+        let profile = super::parse_profile(&document);
+        assert_eq!(
+            Some("https://lain.bgm.tv/pic/user/l/000/00/00/1.jpg?r=1700000000"),
+            profile.avatar.as_deref()
+        );
+        assert_eq!(Some("Bangumi 番组计划"), profile.sign.as_deref());
+        assert_eq!(Some("Hello"), profile.bio.as_deref());
+        assert_eq!(2, profile.network_services.len());
+        assert_eq!("Twitter", profile.network_services[0].service);
+        assert_eq!("sai", profile.network_services[0].value);
+        assert_eq!(
+            Some("https://twitter.com/sai"),
+            profile.network_services[0].url.as_deref()
+        );
+        assert_eq!("sai_psn", profile.network_services[1].value);
+        assert_eq!(None, profile.network_services[1].url);
+    }
+
+    #[test]
+    fn test_parse_collection_list() {
+        let html = r#"<ul id="browserItemList" class="browserFull">
+            <li id="item_253" class="item odd clearit"><div class="inner">
+            <h3><a href="/subject/253" class="l">星际牛仔</a> <small class="grey">カウボーイビバップ</small></h3>
+            <p class="collectInfo"><span class="tip_j">2023-3-2</span><span class="tip_i">/</span><span class="starstop-s"><span class="starlight stars9"></span></span> <span class="tip">标签: 科幻 经典</span></p>
+            <div id="comment_box"><div class="item"><div class="text_main_even"><div class="text">神作</div></div></div></div>
+            </div></li>
+            <li id="item_326" class="item even clearit"><div class="inner">
+            <h3><a href="/subject/326" class="l">攻壳机动队</a></h3>
+            <p class="collectInfo"><span class="tip_j">2024-1-5</span></p>
+            </div></li>
+            </ul>
+            <div id="multipage"><div class="page_inner"><strong class="p_cur">1</strong><a href="?page=2" class="p">2</a><a href="?page=2" class="p">››</a></div></div>"#;
+        let page = super::parse_collection_list(html).unwrap();
+        assert!(page.has_next);
+        assert_eq!(2, page.items.len());
+        let item = &page.items[0];
+        assert_eq!(253, item.subject_id);
+        assert_eq!("星际牛仔", item.title);
+        assert_eq!(Some("カウボーイビバップ"), item.subtitle.as_deref());
+        assert_eq!(Some(9), item.rating);
+        assert_eq!(vec!["科幻", "经典"], item.tags.0);
+        assert_eq!(Some("神作"), item.comment.as_deref());
+        assert_eq!(crate::common::parse_time("2023-3-2").ok(), item.marked_at);
+        let item = &page.items[1];
+        assert_eq!(None, item.rating);
+        assert!(item.tags.0.is_empty());
+        assert_eq!(None, item.comment);
+
+        let last = super::parse_collection_list(r#"<ul id="browserItemList"></ul>"#).unwrap();
+        assert!(!last.has_next);
+        assert!(last.items.is_empty());
+    }
+
+    #[test]
+    fn test_parse_friends() {
+        let html = r#"<ul id="memberUserList" class="usersMedium">
+            <li class="user"><div class="userContainer">
+            <a href="/user/sai" class="avatar"><span class="avatarNeue avatarReSize64 ll"></span></a>
+            <strong><a href="/user/sai" class="avatar">Sai</a></strong>
+            </div></li>
+            <li class="user"><div class="userContainer">
+            <strong><a href="/user/42" class="avatar"> 42号 </a></strong>
+            </div></li>
+            </ul>"#;
+        let friends = super::parse_friends(html).unwrap();
+        assert_eq!(2, friends.len());
+        assert_eq!(super::Uid::Sid("sai".to_string()), friends[0].uid);
+        assert_eq!("Sai", friends[0].name);
+        assert_eq!(super::Uid::Nid(42), friends[1].uid);
+        assert_eq!("42号", friends[1].name);
+        assert!(super::parse_friends("<ul></ul>").unwrap().is_empty());
+    }
+
     // #[test]
     // fn test_parse_timeline_name_history() {
     //     let html = fs::read_to_string(".cache/sai_timeline_1.html").unwrap();
@@ -57,9 +57,10 @@ pub async fn run_task_retry(task: &dyn Task) -> anyhow::Result<()> {
 pub async fn run() -> anyhow::Result<()> {
     tracing::info!("Scheduler started");
     let scheduler = tokio_cron_scheduler::JobScheduler::new().await?;
-    let jobs = vec![onair::Task::new()]
+    let tasks: Vec<Arc<dyn Task>> = vec![Arc::new(onair::Task::new()), Arc::new(user::Task::new())];
+    let jobs = tasks
         .into_iter()
-        .map(|task| add_job(&scheduler, Arc::new(task)))
+        .map(|task| add_job(&scheduler, task))
         .collect::<Vec<_>>();
     join_all(jobs).await;
     scheduler.start().await?;
@@ -27,6 +27,9 @@ impl super::Task for Task {
     }
 
     fn run(&self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>> {
-        Box::pin(async move { Ok(()) })
+        Box::pin(async move {
+            interface::user::refresh_expired().await?;
+            Ok(())
+        })
     }
 }
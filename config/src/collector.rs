@@ -1,5 +1,3 @@
-use rand::seq::IndexedRandom;
-
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Config {
     #[serde(default)]
@@ -19,21 +17,56 @@ impl Default for Config {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct OnAir {
-    #[serde(default = "OnAir::default_mirror")]
-    pub mirror: String,
+    /// bangumi-data `data.json` sources tried in order: URLs or local file
+    /// paths. A single `mirror` string is accepted too.
+    #[serde(
+        default = "OnAir::default_mirrors",
+        alias = "mirror",
+        deserialize_with = "OnAir::deserialize_mirrors"
+    )]
+    pub mirrors: Vec<String>,
+    /// Items parsed and upserted at a time while ingesting.
+    #[serde(default = "OnAir::default_batch_size")]
+    pub batch_size: usize,
 }
 
 impl OnAir {
-    pub fn default_mirror() -> String {
-        "https://github.com/bangumi-data/bangumi-data/raw/refs/heads/master/dist/data.json"
-            .to_string()
+    pub fn default_mirrors() -> Vec<String> {
+        vec![
+            "https://github.com/bangumi-data/bangumi-data/raw/refs/heads/master/dist/data.json"
+                .to_string(),
+            "https://cdn.jsdelivr.net/npm/bangumi-data/dist/data.json".to_string(),
+            "https://unpkg.com/bangumi-data/dist/data.json".to_string(),
+        ]
+    }
+
+    fn deserialize_mirrors<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Mirrors {
+            One(String),
+            Many(Vec<String>),
+        }
+        let mirrors = match serde::Deserialize::deserialize(deserializer)? {
+            Mirrors::One(mirror) => vec![mirror],
+            Mirrors::Many(mirrors) => mirrors,
+        };
+        Ok(mirrors)
+    }
+
+    pub fn default_batch_size() -> usize {
+        500
     }
 }
 
 impl Default for OnAir {
     fn default() -> Self {
         Self {
-            mirror: OnAir::default_mirror(),
+            mirrors: OnAir::default_mirrors(),
+            batch_size: OnAir::default_batch_size(),
         }
     }
 }
@@ -87,6 +120,19 @@ pub struct User {
     pub fresh_duration: UserFreshDuration,
     #[serde(default = "User::default_active_month")]
     pub active_month: u32,
+    /// Expired users refreshed at a time by the user refresh task.
+    #[serde(default = "User::default_refresh_batch_size")]
+    pub refresh_batch_size: u64,
+    /// Users found only through friends pages fetched per refresh run,
+    /// `0` disables discovery.
+    #[serde(default = "User::default_discover_batch_size")]
+    pub discover_batch_size: u64,
+    /// Days a friends page stays fresh.
+    #[serde(default = "User::default_friends_fresh_duration")]
+    pub friends_fresh_duration: i64,
+    /// Expired friends pages fetched per refresh run, `0` disables them.
+    #[serde(default = "User::default_friends_batch_size")]
+    pub friends_batch_size: u64,
 }
 
 impl User {
@@ -102,12 +148,20 @@ impl User {
         6
     }
 
-    pub fn random_origin(&self) -> &String {
-        let mut rng = rand::rng();
-        self.origins
-            .as_slice()
-            .choose(&mut rng)
-            .expect("No origins available")
+    pub fn default_refresh_batch_size() -> u64 {
+        50
+    }
+
+    pub fn default_discover_batch_size() -> u64 {
+        20
+    }
+
+    pub fn default_friends_fresh_duration() -> i64 {
+        7
+    }
+
+    pub fn default_friends_batch_size() -> u64 {
+        20
     }
 }
 
@@ -117,6 +171,10 @@ impl Default for User {
             origins: User::default_origins(),
             fresh_duration: UserFreshDuration::default(),
             active_month: User::default_active_month(),
+            refresh_batch_size: User::default_refresh_batch_size(),
+            discover_batch_size: User::default_discover_batch_size(),
+            friends_fresh_duration: User::default_friends_fresh_duration(),
+            friends_batch_size: User::default_friends_batch_size(),
         }
     }
 }
@@ -7,6 +7,8 @@ pub struct Fetcher {
     #[serde(default = "Fetcher::default_use_proxy")]
     pub use_proxy: bool,
     pub headers: Option<HashMap<String, String>>,
+    /// Shared by every request of the client, whichever mirror it goes to.
+    pub rate_limit: Option<RateLimit>,
 }
 
 impl Fetcher {
@@ -22,10 +24,25 @@ impl Default for Fetcher {
             timeout_secs: None,
             use_proxy: Self::default_use_proxy(),
             headers: None,
+            rate_limit: None,
         }
     }
 }
 
+/// Token bucket refilled at `per_second` and holding at most `burst`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    #[serde(default = "RateLimit::default_burst")]
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn default_burst() -> u32 {
+        1
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Clients {
     #[serde(default)]
@@ -71,12 +88,48 @@ impl Default for Proxy {
     }
 }
 
+/// Circuit breaker of each origin requested by any client.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Health {
+    /// Consecutive failures that eject an origin.
+    #[serde(default = "Health::default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long an ejected origin is skipped before a probe request.
+    #[serde(default = "Health::default_open_secs")]
+    pub open_secs: u64,
+}
+
+impl Health {
+    pub fn default_failure_threshold() -> u32 {
+        5
+    }
+
+    pub fn default_open_secs() -> u64 {
+        60
+    }
+
+    pub fn open_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.open_secs)
+    }
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            failure_threshold: Self::default_failure_threshold(),
+            open_secs: Self::default_open_secs(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Config {
     #[serde(default)]
     pub proxy: Proxy,
     #[serde(default)]
     pub clients: Clients,
+    #[serde(default)]
+    pub health: Health,
 }
 
 impl Default for Config {
@@ -84,6 +137,7 @@ impl Default for Config {
         Self {
             proxy: Proxy::default(),
             clients: Clients::default(),
+            health: Health::default(),
         }
     }
 }
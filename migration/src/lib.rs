@@ -5,6 +5,19 @@ mod m20251007_052102_create_user_table;
 mod m20251008_034436_create_key_value_table;
 mod m20251011_062841_alter_user_timestamp;
 mod m20251011_112458_alter_user_timestamp;
+mod m20261018_100000_alter_on_air_columns;
+mod m20261018_110000_create_on_air_title_table;
+mod m20261018_120000_alter_on_air_removed_at;
+mod m20261018_130000_create_on_air_change_table;
+mod m20261018_140000_create_user_name_table;
+mod m20261018_150000_convert_user_names;
+mod m20261018_160000_create_user_collection_snapshot_table;
+mod m20261018_170000_create_user_sid_alias_table;
+mod m20261018_180000_create_user_list_indexes;
+mod m20261018_190000_create_user_state_log_table;
+mod m20261018_200000_create_user_profile_history_table;
+mod m20261018_210000_create_user_collection_table;
+mod m20261018_220000_create_user_friend_table;
 
 pub struct Migrator;
 
@@ -17,6 +30,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20251008_034436_create_key_value_table::Migration),
             Box::new(m20251011_062841_alter_user_timestamp::Migration),
             Box::new(m20251011_112458_alter_user_timestamp::Migration),
+            Box::new(m20261018_100000_alter_on_air_columns::Migration),
+            Box::new(m20261018_110000_create_on_air_title_table::Migration),
+            Box::new(m20261018_120000_alter_on_air_removed_at::Migration),
+            Box::new(m20261018_130000_create_on_air_change_table::Migration),
+            Box::new(m20261018_140000_create_user_name_table::Migration),
+            Box::new(m20261018_150000_convert_user_names::Migration),
+            Box::new(m20261018_160000_create_user_collection_snapshot_table::Migration),
+            Box::new(m20261018_170000_create_user_sid_alias_table::Migration),
+            Box::new(m20261018_180000_create_user_list_indexes::Migration),
+            Box::new(m20261018_190000_create_user_state_log_table::Migration),
+            Box::new(m20261018_200000_create_user_profile_history_table::Migration),
+            Box::new(m20261018_210000_create_user_collection_table::Migration),
+            Box::new(m20261018_220000_create_user_friend_table::Migration),
         ]
     }
 }
@@ -0,0 +1,81 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OnAir::Table)
+                    .add_column(timestamp_with_time_zone_null(OnAir::Begin))
+                    .add_column(timestamp_with_time_zone_null(OnAir::End))
+                    .add_column(string_null(OnAir::ItemType))
+                    .add_column(string_null(OnAir::Lang))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "on_air" SET
+                    "begin" = NULLIF("data"->>'begin', '')::timestamptz,
+                    "end" = NULLIF("data"->>'end', '')::timestamptz,
+                    "item_type" = "data"->>'type',
+                    "lang" = "data"->>'lang'"#,
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OnAir::Table)
+                    .modify_column(ColumnDef::new(OnAir::ItemType).string().not_null())
+                    .modify_column(ColumnDef::new(OnAir::Lang).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        for (name, col) in [
+            ("idx_on_air_begin", OnAir::Begin),
+            ("idx_on_air_end", OnAir::End),
+            ("idx_on_air_item_type", OnAir::ItemType),
+            ("idx_on_air_lang", OnAir::Lang),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(OnAir::Table)
+                        .col(col)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OnAir::Table)
+                    .drop_column(OnAir::Begin)
+                    .drop_column(OnAir::End)
+                    .drop_column(OnAir::ItemType)
+                    .drop_column(OnAir::Lang)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum OnAir {
+    Table,
+    Begin,
+    End,
+    ItemType,
+    Lang,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(OnAirTitle::Table)
+                    .if_not_exists()
+                    .col(pk_auto(OnAirTitle::Id))
+                    .col(integer(OnAirTitle::Subject))
+                    .col(text(OnAirTitle::Title))
+                    .col(text(OnAirTitle::Normalized))
+                    .col(string(OnAirTitle::Lang))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_on_air_title_subject")
+                    .table(OnAirTitle::Table)
+                    .col(OnAirTitle::Subject)
+                    .to_owned(),
+            )
+            .await?;
+        // exact and prefix matches
+        db.execute_unprepared(
+            r#"CREATE INDEX "idx_on_air_title_normalized" ON "on_air_title" ("normalized" text_pattern_ops)"#,
+        )
+        .await?;
+        // substring and fuzzy matches
+        db.execute_unprepared(
+            r#"CREATE INDEX "idx_on_air_title_normalized_trgm" ON "on_air_title" USING gin ("normalized" gin_trgm_ops)"#,
+        )
+        .await?;
+        // build the index from existing rows, later flushes keep it in sync
+        db.execute_unprepared(
+            r#"INSERT INTO "on_air_title" ("subject", "title", "normalized", "lang")
+            SELECT "subject", "data"->>'title', lower(regexp_replace(btrim("data"->>'title'), '\s+', ' ', 'g')), "data"->>'lang'
+            FROM "on_air"
+            UNION ALL
+            SELECT "subject", t.title, lower(regexp_replace(btrim(t.title), '\s+', ' ', 'g')), tr.key
+            FROM "on_air",
+                jsonb_each("data"->'titleTranslate') AS tr,
+                jsonb_array_elements_text(tr.value) AS t(title)"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OnAirTitle::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum OnAirTitle {
+    Table,
+    Id,
+    Subject,
+    Title,
+    Normalized,
+    Lang,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OnAirChange::Table)
+                    .if_not_exists()
+                    .col(big_integer(OnAirChange::Id).auto_increment().primary_key())
+                    .col(integer(OnAirChange::Subject))
+                    .col(string(OnAirChange::Kind))
+                    .col(json_binary(OnAirChange::Diff))
+                    .col(timestamp_with_time_zone(OnAirChange::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_on_air_change_subject")
+                    .table(OnAirChange::Table)
+                    .col(OnAirChange::Subject)
+                    .col(OnAirChange::Id)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_on_air_change_created_at")
+                    .table(OnAirChange::Table)
+                    .col(OnAirChange::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OnAirChange::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum OnAirChange {
+    Table,
+    Id,
+    Subject,
+    Kind,
+    Diff,
+    CreatedAt,
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        // also created with the on-air title index, repeated so this
+        // migration stands on its own
+        db.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm")
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserName::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserName::Id).auto_increment().primary_key())
+                    .col(uuid(UserName::UserId))
+                    .col(text(UserName::Name))
+                    .col(text(UserName::Normalized))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_name_user_id")
+                            .from(UserName::Table, UserName::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_name_user_id_name")
+                    .table(UserName::Table)
+                    .col(UserName::UserId)
+                    .col(UserName::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_name_normalized")
+                    .table(UserName::Table)
+                    .col(UserName::Normalized)
+                    .to_owned(),
+            )
+            .await?;
+        // fuzzy matches
+        db.execute_unprepared(
+            r#"CREATE INDEX "idx_user_name_normalized_trgm" ON "user_name" USING gin ("normalized" gin_trgm_ops)"#,
+        )
+        .await?;
+        // current names and every name in the name history of existing users
+        db.execute_unprepared(
+            r#"INSERT INTO "user_name" ("user_id", "name", "normalized")
+            SELECT "id", "name", lower(regexp_replace(btrim("name"), '\s+', ' ', 'g'))
+            FROM "user"
+            WHERE btrim(coalesce("name", '')) <> ''
+            UNION
+            SELECT "id", n.name, lower(regexp_replace(btrim(n.name), '\s+', ' ', 'g'))
+            FROM "user",
+                jsonb_array_elements_text(
+                    coalesce("extra"->'name_history'->'names', '[]'::jsonb)
+                ) AS n(name)
+            WHERE btrim(n.name) <> ''
+            ON CONFLICT DO NOTHING"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserName::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserName {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Normalized,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // old name sets become records without first/last seen times
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "user" SET "extra" = jsonb_set(
+                    "extra",
+                    '{name_history,names}',
+                    (
+                        SELECT coalesce(jsonb_agg(jsonb_build_object('name', n.name)), '[]'::jsonb)
+                        FROM jsonb_array_elements_text("extra"->'name_history'->'names') AS n(name)
+                    )
+                )
+                WHERE jsonb_typeof("extra"->'name_history'->'names') = 'array'
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM jsonb_array_elements("extra"->'name_history'->'names') AS n(value)
+                        WHERE jsonb_typeof(n.value) <> 'string'
+                    )"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"UPDATE "user" SET "extra" = jsonb_set(
+                    "extra",
+                    '{name_history,names}',
+                    (
+                        SELECT coalesce(jsonb_agg(n.value->'name'), '[]'::jsonb)
+                        FROM jsonb_array_elements("extra"->'name_history'->'names') AS n(value)
+                    )
+                )
+                WHERE jsonb_typeof("extra"->'name_history'->'names') = 'array'"#,
+            )
+            .await?;
+        Ok(())
+    }
+}
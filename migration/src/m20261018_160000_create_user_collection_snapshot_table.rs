@@ -0,0 +1,82 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserCollectionSnapshot::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(UserCollectionSnapshot::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(uuid(UserCollectionSnapshot::UserId))
+                    .col(json_binary(UserCollectionSnapshot::Collections))
+                    .col(timestamp_with_time_zone(UserCollectionSnapshot::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_collection_snapshot_user_id")
+                            .from(
+                                UserCollectionSnapshot::Table,
+                                UserCollectionSnapshot::UserId,
+                            )
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_collection_snapshot_user_id_created_at")
+                    .table(UserCollectionSnapshot::Table)
+                    .col(UserCollectionSnapshot::UserId)
+                    .col(UserCollectionSnapshot::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        // the current counts of existing users start their series
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "user_collection_snapshot" ("user_id", "collections", "created_at")
+                SELECT "id", "extra"->'collections', "update_at"
+                FROM "user"
+                WHERE jsonb_typeof("extra"->'collections') = 'object'"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(UserCollectionSnapshot::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserCollectionSnapshot {
+    Table,
+    Id,
+    UserId,
+    Collections,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
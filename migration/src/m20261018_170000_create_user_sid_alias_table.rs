@@ -0,0 +1,57 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserSidAlias::Table)
+                    .if_not_exists()
+                    .col(text(UserSidAlias::Sid).primary_key())
+                    .col(uuid(UserSidAlias::UserId))
+                    .col(timestamp_with_time_zone(UserSidAlias::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_sid_alias_user_id")
+                            .from(UserSidAlias::Table, UserSidAlias::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_sid_alias_user_id")
+                    .table(UserSidAlias::Table)
+                    .col(UserSidAlias::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserSidAlias::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserSidAlias {
+    Table,
+    Sid,
+    UserId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_state")
+                    .table(User::Table)
+                    .col(User::State)
+                    .to_owned(),
+            )
+            .await?;
+        // keyset pagination orders by the sort column, then by id
+        for (name, col) in [
+            ("idx_user_join_time_id", User::JoinTime),
+            ("idx_user_last_active_id", User::LastActive),
+            ("idx_user_update_at_id", User::UpdateAt),
+        ] {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(name)
+                        .table(User::Table)
+                        .col(col)
+                        .col(User::Id)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX "idx_user_lower_name" ON "user" (lower("name") text_pattern_ops)"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for name in [
+            "idx_user_lower_name",
+            "idx_user_update_at_id",
+            "idx_user_last_active_id",
+            "idx_user_join_time_id",
+            "idx_user_state",
+        ] {
+            manager
+                .drop_index(Index::drop().name(name).table(User::Table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+    State,
+    JoinTime,
+    LastActive,
+    UpdateAt,
+}
@@ -0,0 +1,83 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserStateLog::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserStateLog::Id).auto_increment().primary_key())
+                    .col(uuid(UserStateLog::UserId))
+                    .col(string_null(UserStateLog::FromState))
+                    .col(string(UserStateLog::ToState))
+                    .col(timestamp_with_time_zone(UserStateLog::ObservedAt))
+                    .col(text_null(UserStateLog::Source))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_state_log_user_id")
+                            .from(UserStateLog::Table, UserStateLog::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_state_log_user_id_observed_at")
+                    .table(UserStateLog::Table)
+                    .col(UserStateLog::UserId)
+                    .col(UserStateLog::ObservedAt)
+                    .to_owned(),
+            )
+            .await?;
+        // daily counts
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_state_log_observed_at")
+                    .table(UserStateLog::Table)
+                    .col(UserStateLog::ObservedAt)
+                    .to_owned(),
+            )
+            .await?;
+        // the current state of existing users is where their log starts
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO "user_state_log" ("user_id", "to_state", "observed_at")
+                SELECT "id", "state", "update_at" FROM "user""#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserStateLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserStateLog {
+    Table,
+    Id,
+    UserId,
+    FromState,
+    ToState,
+    ObservedAt,
+    Source,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserProfileHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(UserProfileHistory::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(uuid(UserProfileHistory::UserId))
+                    .col(string(UserProfileHistory::Field))
+                    .col(text_null(UserProfileHistory::Value))
+                    .col(timestamp_with_time_zone(UserProfileHistory::FirstSeen))
+                    .col(timestamp_with_time_zone(UserProfileHistory::LastSeen))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_profile_history_user_id")
+                            .from(UserProfileHistory::Table, UserProfileHistory::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_profile_history_user_id_field")
+                    .table(UserProfileHistory::Table)
+                    .col(UserProfileHistory::UserId)
+                    .col(UserProfileHistory::Field)
+                    .col(UserProfileHistory::LastSeen)
+                    .to_owned(),
+            )
+            .await?;
+        // the same avatar showing up on another user
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"CREATE INDEX "idx_user_profile_history_avatar" ON "user_profile_history" ("value")
+                WHERE "field" = 'avatar'"#,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserProfileHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserProfileHistory {
+    Table,
+    Id,
+    UserId,
+    Field,
+    Value,
+    FirstSeen,
+    LastSeen,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
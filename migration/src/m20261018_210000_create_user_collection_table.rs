@@ -0,0 +1,141 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserCollection::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(UserCollection::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(uuid(UserCollection::UserId))
+                    .col(string(UserCollection::SubjectType))
+                    .col(integer(UserCollection::SubjectId))
+                    .col(string(UserCollection::State))
+                    .col(text(UserCollection::Title))
+                    .col(text_null(UserCollection::Subtitle))
+                    .col(small_integer_null(UserCollection::Rating))
+                    .col(json_binary(UserCollection::Tags))
+                    .col(text_null(UserCollection::Comment))
+                    .col(timestamp_with_time_zone_null(UserCollection::MarkedAt))
+                    .col(timestamp_with_time_zone(UserCollection::UpdateAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_collection_user_id")
+                            .from(UserCollection::Table, UserCollection::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_collection_user_id_subject")
+                    .table(UserCollection::Table)
+                    .col(UserCollection::UserId)
+                    .col(UserCollection::SubjectType)
+                    .col(UserCollection::SubjectId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_collection_subject_id")
+                    .table(UserCollection::Table)
+                    .col(UserCollection::SubjectId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserCollectionList::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(UserCollectionList::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(uuid(UserCollectionList::UserId))
+                    .col(string(UserCollectionList::SubjectType))
+                    .col(string(UserCollectionList::State))
+                    .col(timestamp_with_time_zone(UserCollectionList::SyncedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_collection_list_user_id")
+                            .from(UserCollectionList::Table, UserCollectionList::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_collection_list_user_id_list")
+                    .table(UserCollectionList::Table)
+                    .col(UserCollectionList::UserId)
+                    .col(UserCollectionList::SubjectType)
+                    .col(UserCollectionList::State)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserCollectionList::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(UserCollection::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserCollection {
+    Table,
+    Id,
+    UserId,
+    SubjectType,
+    SubjectId,
+    State,
+    Title,
+    Subtitle,
+    Rating,
+    Tags,
+    Comment,
+    MarkedAt,
+    UpdateAt,
+}
+
+#[derive(DeriveIden)]
+enum UserCollectionList {
+    Table,
+    Id,
+    UserId,
+    SubjectType,
+    State,
+    SyncedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserFriend::Table)
+                    .if_not_exists()
+                    .col(big_integer(UserFriend::Id).auto_increment().primary_key())
+                    .col(uuid(UserFriend::UserId))
+                    .col(text(UserFriend::FriendUid))
+                    .col(integer_null(UserFriend::FriendNid))
+                    .col(text(UserFriend::Name))
+                    .col(timestamp_with_time_zone(UserFriend::FirstSeen))
+                    .col(timestamp_with_time_zone(UserFriend::LastSeen))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_friend_user_id")
+                            .from(UserFriend::Table, UserFriend::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_friend_user_id_friend_uid")
+                    .table(UserFriend::Table)
+                    .col(UserFriend::UserId)
+                    .col(UserFriend::FriendUid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_friend_friend_uid")
+                    .table(UserFriend::Table)
+                    .col(UserFriend::FriendUid)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_friend_friend_nid")
+                    .table(UserFriend::Table)
+                    .col(UserFriend::FriendNid)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserFriend::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserFriend {
+    Table,
+    Id,
+    UserId,
+    FriendUid,
+    FriendNid,
+    Name,
+    FirstSeen,
+    LastSeen,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    Table,
+    Id,
+}
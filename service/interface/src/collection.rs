@@ -1,3 +1,12 @@
 pub mod kv;
 pub mod onair;
+pub mod onair_change;
+pub mod onair_title;
 pub mod user;
+pub mod user_collection;
+pub mod user_collection_snapshot;
+pub mod user_friend;
+pub mod user_name;
+pub mod user_profile_history;
+pub mod user_sid_alias;
+pub mod user_state_log;
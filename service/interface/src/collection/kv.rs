@@ -1,5 +1,6 @@
 use db::prelude::*;
 use db::sea_query::OnConflict;
+use model::common::onair::SiteMetaMap;
 use model::entity::key_value::{ActiveModel, Column, Entity};
 use serde_json::json;
 use std::str::FromStr;
@@ -27,23 +28,39 @@ pub async fn set(db: &impl ConnectionTrait, key: &str, value: &Json) -> anyhow::
     Ok(())
 }
 
+/// HTTP cache validators of the last download, only valid for `mirror`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    pub mirror: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
 pub struct OnAir {
     pub hash: Option<String>,
     pub update_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub validators: Option<Validators>,
 }
 
 impl From<Json> for OnAir {
     fn from(value: Json) -> Self {
-        let hash = value
-            .get("hash")
-            .and_then(|h| h.as_str())
-            .map(|s| s.to_string());
+        let str_of = |key: &str| value.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let hash = str_of("hash");
 
         let update_at = value
             .get("update_at")
             .and_then(|t| t.as_str())
             .and_then(|s| DateTimeUtc::from_str(s).ok());
-        Self { hash, update_at }
+        let validators = str_of("mirror").map(|mirror| Validators {
+            mirror,
+            etag: str_of("etag"),
+            last_modified: str_of("last_modified"),
+        });
+        Self {
+            hash,
+            update_at,
+            validators,
+        }
     }
 }
 
@@ -52,6 +69,7 @@ impl Default for OnAir {
         Self {
             hash: None,
             update_at: None,
+            validators: None,
         }
     }
 }
@@ -68,17 +86,25 @@ impl OnAir {
         db: &impl ConnectionTrait,
         hash: &str,
         update_at: chrono::DateTime<chrono::Utc>,
+        validators: Option<&Validators>,
     ) -> anyhow::Result<()> {
         let j = json!({
             "hash": hash,
             "update_at": update_at.to_rfc3339(),
+            "mirror": validators.map(|v| &v.mirror),
+            "etag": validators.and_then(|v| v.etag.as_ref()),
+            "last_modified": validators.and_then(|v| v.last_modified.as_ref()),
         });
         set(db, "onair", &j).await?;
         Ok(())
     }
 
-    pub async fn update(db: &impl ConnectionTrait, hash: &str) -> anyhow::Result<()> {
-        Self::set(db, hash, chrono::Utc::now()).await
+    pub async fn update(
+        db: &impl ConnectionTrait,
+        hash: &str,
+        validators: Option<&Validators>,
+    ) -> anyhow::Result<()> {
+        Self::set(db, hash, chrono::Utc::now(), validators).await
     }
 
     pub fn diff_hash(&self, hash: &str) -> bool {
@@ -88,3 +114,23 @@ impl OnAir {
         }
     }
 }
+
+/// bangumi-data `siteMeta`, replaced on every on-air flush.
+pub struct SiteMeta;
+
+impl SiteMeta {
+    const KEY: &str = "onair_site_meta";
+
+    pub async fn get(db: &impl ConnectionTrait) -> anyhow::Result<SiteMetaMap> {
+        let rec = get(db, Self::KEY)
+            .await?
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(rec)
+    }
+
+    pub async fn set(db: &impl ConnectionTrait, site_meta: &SiteMetaMap) -> anyhow::Result<()> {
+        set(db, Self::KEY, &serde_json::to_value(site_meta)?).await
+    }
+}
@@ -1,7 +1,10 @@
 use db::prelude::*;
+use db::sea_query::Expr;
 use db::sea_query::OnConflict;
-use model::common::onair::{BangumiItemMap, SubjectIds};
-use model::entity::on_air::{ActiveModel, Column, Entity};
+use db::{Condition, PaginatorTrait, QueryOrder, QuerySelect};
+use model::common::onair::bangumi_data::{ItemType, Language};
+use model::common::onair::{BangumiItemMap, SubjectId, SubjectIds};
+use model::entity::on_air::{ActiveModel, Column, Entity, Model};
 
 pub async fn upsert_many(db: &impl ConnectionTrait, items: BangumiItemMap) -> anyhow::Result<()> {
     let models = items
@@ -9,12 +12,27 @@ pub async fn upsert_many(db: &impl ConnectionTrait, items: BangumiItemMap) -> an
         .map(|(id, item)| ActiveModel {
             subject: db::Set(*id),
             data: db::Set(item.to_owned()),
+            begin: db::Set(item.begin_time()),
+            end: db::Set(item.end_time()),
+            item_type: db::Set(item.item_type.clone()),
+            lang: db::Set(item.lang.clone()),
+            removed_at: db::Set(None),
         })
         .collect::<Vec<_>>();
+    if models.is_empty() {
+        return Ok(());
+    }
     Entity::insert_many(models)
         .on_conflict(
             OnConflict::column(Column::Subject)
-                .update_column(Column::Data)
+                .update_columns([
+                    Column::Data,
+                    Column::Begin,
+                    Column::End,
+                    Column::ItemType,
+                    Column::Lang,
+                    Column::RemovedAt,
+                ])
                 .to_owned(),
         )
         .exec(db)
@@ -22,11 +40,79 @@ pub async fn upsert_many(db: &impl ConnectionTrait, items: BangumiItemMap) -> an
     Ok(())
 }
 
+/// Tombstone the live items among `ids`.
+pub async fn mark_removed(
+    db: &impl ConnectionTrait,
+    ids: &SubjectIds,
+    at: DateTimeUtc,
+) -> anyhow::Result<u64> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let result = Entity::update_many()
+        .col_expr(Column::RemovedAt, Expr::value(at))
+        .filter(Column::Subject.is_in(ids.clone()))
+        .filter(Column::RemovedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// Subjects of every item that is not tombstoned.
+pub async fn find_live_subject_ids(db: &impl ConnectionTrait) -> anyhow::Result<SubjectIds> {
+    let ids = Entity::find()
+        .select_only()
+        .column(Column::Subject)
+        .filter(Column::RemovedAt.is_null())
+        .into_tuple::<SubjectId>()
+        .all(db)
+        .await?;
+    Ok(ids.into_iter().collect())
+}
+
+/// Rows of `ids`, tombstoned ones only when `include_removed`.
+pub async fn find_models_by_subject_ids(
+    db: &impl ConnectionTrait,
+    ids: &SubjectIds,
+    include_removed: bool,
+) -> anyhow::Result<Vec<Model>> {
+    let mut query = Entity::find().filter(Column::Subject.is_in(ids.clone()));
+    if !include_removed {
+        query = query.filter(Column::RemovedAt.is_null());
+    }
+    Ok(query.all(db).await?)
+}
+
 pub async fn find_by_subject_ids(
     db: &impl ConnectionTrait,
     ids: &SubjectIds,
 ) -> anyhow::Result<BangumiItemMap> {
-    let query = Entity::find().filter(Column::Subject.is_in(ids.clone()));
+    let query = Entity::find()
+        .filter(Column::Subject.is_in(ids.clone()))
+        .filter(Column::RemovedAt.is_null());
+    let items = query.all(db).await?;
+    let items: BangumiItemMap = items
+        .into_iter()
+        .map(|item| (item.subject, item.data))
+        .collect();
+    Ok(items)
+}
+
+/// Live items whose `[begin, end]` overlaps `[from, to)`; items without `end`
+/// are treated as still airing.
+pub async fn find_by_date_range(
+    db: &impl ConnectionTrait,
+    from: DateTimeUtc,
+    to: DateTimeUtc,
+) -> anyhow::Result<BangumiItemMap> {
+    let query = Entity::find()
+        .filter(Column::Begin.lt(to))
+        .filter(
+            Condition::any()
+                .add(Column::End.is_null())
+                .add(Column::End.gte(from)),
+        )
+        .filter(Column::RemovedAt.is_null());
     let items = query.all(db).await?;
     let items: BangumiItemMap = items
         .into_iter()
@@ -34,3 +120,78 @@ pub async fn find_by_subject_ids(
         .collect();
     Ok(items)
 }
+
+/// Filter over the indexed on-air columns.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub begin_after: Option<DateTimeUtc>,
+    pub begin_before: Option<DateTimeUtc>,
+    pub item_type: Option<ItemType>,
+    pub lang: Option<Language>,
+    pub include_removed: bool,
+}
+
+impl Filter {
+    pub fn set_begin_after(&mut self, begin_after: Option<DateTimeUtc>) -> &mut Self {
+        self.begin_after = begin_after;
+        self
+    }
+
+    pub fn set_begin_before(&mut self, begin_before: Option<DateTimeUtc>) -> &mut Self {
+        self.begin_before = begin_before;
+        self
+    }
+
+    pub fn set_item_type(&mut self, item_type: Option<ItemType>) -> &mut Self {
+        self.item_type = item_type;
+        self
+    }
+
+    pub fn set_lang(&mut self, lang: Option<Language>) -> &mut Self {
+        self.lang = lang;
+        self
+    }
+
+    pub fn set_include_removed(&mut self, include_removed: bool) -> &mut Self {
+        self.include_removed = include_removed;
+        self
+    }
+
+    pub fn condition(&self) -> Condition {
+        let mut cond = Condition::all();
+        if !self.include_removed {
+            cond = cond.add(Column::RemovedAt.is_null());
+        }
+        if let Some(begin_after) = self.begin_after {
+            cond = cond.add(Column::Begin.gte(begin_after));
+        }
+        if let Some(begin_before) = self.begin_before {
+            cond = cond.add(Column::Begin.lt(begin_before));
+        }
+        if let Some(item_type) = &self.item_type {
+            cond = cond.add(Column::ItemType.eq(item_type.clone()));
+        }
+        if let Some(lang) = &self.lang {
+            cond = cond.add(Column::Lang.eq(lang.clone()));
+        }
+        cond
+    }
+}
+
+/// One page of items ordered by `begin`, with the total number of matches.
+/// `page` starts at 0.
+pub async fn find_by_filter(
+    db: &impl ConnectionTrait,
+    filter: &Filter,
+    page: u64,
+    limit: u64,
+) -> anyhow::Result<(Vec<Model>, u64)> {
+    let paginator = Entity::find()
+        .filter(filter.condition())
+        .order_by_asc(Column::Begin)
+        .order_by_asc(Column::Subject)
+        .paginate(db, limit);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(page).await?;
+    Ok((items, total))
+}
@@ -0,0 +1,144 @@
+use crate::collection::onair;
+use db::prelude::*;
+use db::sea_query::Expr;
+use db::{Condition, QueryOrder, QuerySelect};
+use model::common::onair::{BangumiItemMap, ChangeKind, Diff, SubjectId, SubjectIds};
+use model::entity::on_air_change::{ActiveModel, Column, Entity, Model};
+use std::collections::HashMap;
+
+/// Rows per insert, well below the Postgres bind parameter limit.
+const CHUNK_SIZE: usize = 1000;
+
+fn change(subject: SubjectId, kind: ChangeKind, diff: Diff, at: DateTimeUtc) -> ActiveModel {
+    ActiveModel {
+        subject: db::Set(subject),
+        kind: db::Set(kind),
+        diff: db::Set(diff),
+        created_at: db::Set(at),
+        ..Default::default()
+    }
+}
+
+/// Record how `items` and the tombstoned `removed` differ from the stored
+/// rows. Must run before they are written.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    items: &BangumiItemMap,
+    removed: &SubjectIds,
+    at: DateTimeUtc,
+) -> anyhow::Result<u64> {
+    let subjects = items.keys().chain(removed).copied().collect::<SubjectIds>();
+    let previous = onair::find_models_by_subject_ids(db, &subjects, true)
+        .await?
+        .into_iter()
+        .map(|m| (m.subject, m))
+        .collect::<HashMap<_, _>>();
+    let mut subjects = items.keys().copied().collect::<Vec<_>>();
+    subjects.sort();
+    let mut models = Vec::new();
+    for subject in subjects {
+        let item = &items[&subject];
+        let (kind, diff) = match previous.get(&subject) {
+            None => (ChangeKind::Added, Diff::added(item)),
+            Some(old) if old.removed_at.is_some() => {
+                (ChangeKind::Restored, Diff::between(&old.data, item))
+            }
+            Some(old) => {
+                let diff = Diff::between(&old.data, item);
+                if diff.is_empty() {
+                    continue;
+                }
+                (ChangeKind::Changed, diff)
+            }
+        };
+        models.push(change(subject, kind, diff, at));
+    }
+    let mut removed = removed.iter().copied().collect::<Vec<_>>();
+    removed.sort();
+    for subject in removed {
+        models.push(change(subject, ChangeKind::Removed, Diff::default(), at));
+    }
+    for chunk in models.chunks(CHUNK_SIZE) {
+        Entity::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(models.len() as u64)
+}
+
+/// Filter over the change feed.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub since: Option<DateTimeUtc>,
+    pub after: Option<i64>,
+    pub kind: Option<ChangeKind>,
+    pub sites_added: bool,
+}
+
+impl Filter {
+    pub fn set_since(&mut self, since: Option<DateTimeUtc>) -> &mut Self {
+        self.since = since;
+        self
+    }
+
+    /// Only changes with an id greater than `after`, for polling.
+    pub fn set_after(&mut self, after: Option<i64>) -> &mut Self {
+        self.after = after;
+        self
+    }
+
+    pub fn set_kind(&mut self, kind: Option<ChangeKind>) -> &mut Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Only changes adding at least one site.
+    pub fn set_sites_added(&mut self, sites_added: bool) -> &mut Self {
+        self.sites_added = sites_added;
+        self
+    }
+
+    pub fn condition(&self) -> Condition {
+        let mut cond = Condition::all();
+        if let Some(since) = self.since {
+            cond = cond.add(Column::CreatedAt.gte(since));
+        }
+        if let Some(after) = self.after {
+            cond = cond.add(Column::Id.gt(after));
+        }
+        if let Some(kind) = self.kind {
+            cond = cond.add(Column::Kind.eq(kind));
+        }
+        if self.sites_added {
+            // empty lists are not serialized
+            cond = cond.add(Expr::cust(r#""diff"->'sitesAdded' IS NOT NULL"#));
+        }
+        cond
+    }
+}
+
+/// Oldest first, at most `limit`.
+pub async fn find_by_filter(
+    db: &impl ConnectionTrait,
+    filter: &Filter,
+    limit: u64,
+) -> anyhow::Result<Vec<Model>> {
+    let changes = Entity::find()
+        .filter(filter.condition())
+        .order_by_asc(Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+    Ok(changes)
+}
+
+/// Every change of `subject`, oldest first.
+pub async fn find_by_subject(
+    db: &impl ConnectionTrait,
+    subject: SubjectId,
+) -> anyhow::Result<Vec<Model>> {
+    let changes = Entity::find()
+        .filter(Column::Subject.eq(subject))
+        .order_by_asc(Column::Id)
+        .all(db)
+        .await?;
+    Ok(changes)
+}
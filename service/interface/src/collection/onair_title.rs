@@ -0,0 +1,106 @@
+use db::Statement;
+use db::prelude::*;
+use model::common::onair::{BangumiItem, BangumiItemMap, SubjectId, SubjectIds};
+use model::common::text::{MatchKind, escape_like, normalize};
+use model::entity::on_air_title::{ActiveModel, Column, Entity};
+
+/// Rows per insert, well below the Postgres bind parameter limit.
+const CHUNK_SIZE: usize = 1000;
+
+fn titles(subject: SubjectId, item: &BangumiItem) -> impl Iterator<Item = ActiveModel> + '_ {
+    let original = std::iter::once((item.lang.to_string(), &item.title));
+    let translated = item
+        .title_translate
+        .iter()
+        .flat_map(|(lang, titles)| titles.iter().map(move |t| (lang.clone(), t)));
+    original
+        .chain(translated)
+        .filter(|(_, title)| !title.trim().is_empty())
+        .map(move |(lang, title)| ActiveModel {
+            subject: db::Set(subject),
+            title: db::Set(title.clone()),
+            normalized: db::Set(normalize(title)),
+            lang: db::Set(lang),
+            ..Default::default()
+        })
+}
+
+/// Drop the title index entries of `subjects`.
+pub async fn delete_many(db: &impl ConnectionTrait, subjects: &SubjectIds) -> anyhow::Result<()> {
+    Entity::delete_many()
+        .filter(Column::Subject.is_in(subjects.iter().copied()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Replace the title index entries of `items`.
+pub async fn replace_many(db: &impl ConnectionTrait, items: &BangumiItemMap) -> anyhow::Result<()> {
+    Entity::delete_many()
+        .filter(Column::Subject.is_in(items.keys().copied()))
+        .exec(db)
+        .await?;
+    let models = items
+        .iter()
+        .flat_map(|(subject, item)| titles(*subject, item))
+        .collect::<Vec<_>>();
+    for chunk in models.chunks(CHUNK_SIZE) {
+        Entity::insert_many(chunk.to_vec()).exec(db).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub subject: SubjectId,
+    pub kind: MatchKind,
+    /// Trigram similarity of the best matching title, in `[0, 1]`.
+    pub score: f32,
+}
+
+/// Subjects with a title matching `q`, exact matches first, then prefix,
+/// substring and trigram (fuzzy) matches, each ordered by similarity.
+pub async fn search(db: &impl ConnectionTrait, q: &str, limit: u64) -> anyhow::Result<Vec<Hit>> {
+    let q = normalize(q);
+    if q.is_empty() {
+        return Ok(Vec::new());
+    }
+    let escaped = escape_like(&q);
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT "subject",
+            MAX(CASE
+                WHEN "normalized" = $1 THEN 3
+                WHEN "normalized" LIKE $2 THEN 2
+                WHEN "normalized" LIKE $3 THEN 1
+                ELSE 0
+            END) AS "rank",
+            MAX(similarity("normalized", $1)) AS "score"
+        FROM "on_air_title"
+        WHERE "normalized" LIKE $3 OR "normalized" % $1
+        GROUP BY "subject"
+        ORDER BY "rank" DESC, "score" DESC, "subject"
+        LIMIT $4"#,
+        [
+            q.clone().into(),
+            format!("{escaped}%").into(),
+            format!("%{escaped}%").into(),
+            (limit as i64).into(),
+        ],
+    );
+    let mut hits = Vec::new();
+    for row in db.query_all(stmt).await? {
+        let rank: i32 = row.try_get("", "rank")?;
+        hits.push(Hit {
+            subject: row.try_get("", "subject")?,
+            kind: match rank {
+                3 => MatchKind::Exact,
+                2 => MatchKind::Prefix,
+                1 => MatchKind::Contains,
+                _ => MatchKind::Fuzzy,
+            },
+            score: row.try_get("", "score")?,
+        });
+    }
+    Ok(hits)
+}
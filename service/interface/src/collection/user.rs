@@ -1,9 +1,34 @@
-use db::{ActiveModelTrait, Set, TryIntoModel};
+use db::sea_query::{Expr, Func};
+use db::{ActiveModelTrait, QueryOrder, QuerySelect, Set, Statement, TryIntoModel};
 use db::{Condition, prelude::*};
-use model::common::user::{Extra, InitUser, NameHistory, NamesUpdate, Uid};
-use model::entity::user::{ActiveModel, Column, Entity, Model};
+use model::common::text::escape_like;
+use model::common::user::{
+    Extra, Friend, FriendList, InitUser, NameHistory, NamesUpdate, Uid, UserSort, UserState,
+    merge_names,
+};
+use model::entity::user::{ActiveModel, Column, Entity, Model, Sid};
 
+use crate::collection::{
+    user_collection, user_collection_snapshot, user_friend, user_name, user_profile_history,
+    user_sid_alias, user_state_log,
+};
+
+/// The user currently known by `uid`, or by `uid` as a former sid.
 pub async fn find_by_uid(db: &impl ConnectionTrait, uid: Uid) -> anyhow::Result<Option<Model>> {
+    if let Some(user) = find_current_by_uid(db, uid.clone()).await? {
+        return Ok(Some(user));
+    }
+    let Uid::Sid(sid) = uid else {
+        return Ok(None);
+    };
+    let Some(alias) = user_sid_alias::find_many(db, vec![sid]).await?.pop() else {
+        return Ok(None);
+    };
+    let user = Entity::find_by_id(alias.user_id).one(db).await?;
+    Ok(user)
+}
+
+async fn find_current_by_uid(db: &impl ConnectionTrait, uid: Uid) -> anyhow::Result<Option<Model>> {
     let cond = match uid.clone() {
         Uid::Nid(nid) => Column::Nid.eq(nid),
         Uid::Sid(sid) => Column::Sid.eq(sid),
@@ -21,6 +46,7 @@ pub async fn find_by_uid(db: &impl ConnectionTrait, uid: Uid) -> anyhow::Result<
 }
 
 async fn insert_user(db: &impl ConnectionTrait, init: InitUser) -> anyhow::Result<Model> {
+    let source = init.source.clone();
     let extra = Extra {
         name_history: init.names_update.map(|nu| NameHistory {
             update_at: chrono::Utc::now(),
@@ -28,6 +54,8 @@ async fn insert_user(db: &impl ConnectionTrait, init: InitUser) -> anyhow::Resul
             names: nu.names,
         }),
         collections: init.collections,
+        profile: init.profile,
+        friends: None,
     };
     let user = ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -46,6 +74,11 @@ async fn insert_user(db: &impl ConnectionTrait, init: InitUser) -> anyhow::Resul
         .one(db)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Failed to fetch inserted user"))?;
+    insert_names(db, &inserted).await?;
+    record_collections(db, &inserted).await?;
+    observe_profile(db, &inserted).await?;
+    let state = inserted.state.clone();
+    user_state_log::record(db, inserted.id, None, state, inserted.update_at, source).await?;
     Ok(inserted)
 }
 
@@ -54,6 +87,17 @@ async fn update_user(
     init: InitUser,
     existing: Model,
 ) -> anyhow::Result<Model> {
+    // keep answering to the sid the user had before
+    if let (Some(old), Some(new)) = (&existing.sid, &init.sid)
+        && old != new
+    {
+        let now = chrono::Utc::now();
+        user_sid_alias::record(db, old.clone(), existing.id, now).await?;
+    }
+    let transition = (existing.state != init.state).then(|| {
+        let from = existing.state.clone();
+        (from, init.state.clone(), init.source.clone())
+    });
     // update fields from init
     let mut extra = existing.extra.clone();
     let mut am: ActiveModel = existing.into();
@@ -66,6 +110,9 @@ async fn update_user(
     am.name = Set(init.name);
     am.state = Set(init.state);
     am.last_active = Set(init.last_active);
+    if let Some(profile) = init.profile {
+        am.extra = Set(extra.update_profile(profile).to_owned());
+    }
     match (init.collections, init.names_update) {
         (None, None) => {}
         (Some(collections), None) => {
@@ -82,7 +129,37 @@ async fn update_user(
     }
     am.update_at = Set(chrono::Utc::now());
     am.clone().save(db).await?;
-    Ok(am.try_into_model()?)
+    let user = am.try_into_model()?;
+    insert_names(db, &user).await?;
+    record_collections(db, &user).await?;
+    observe_profile(db, &user).await?;
+    if let Some((from, to, source)) = transition {
+        user_state_log::record(db, user.id, Some(from), to, user.update_at, source).await?;
+    }
+    Ok(user)
+}
+
+/// Index the current name and the name history of `user` for reverse lookup.
+async fn insert_names(db: &impl ConnectionTrait, user: &Model) -> anyhow::Result<()> {
+    let mut names = vec![&user.name];
+    if let Some(nh) = &user.extra.name_history {
+        names.extend(nh.names.iter().map(|n| &n.name));
+    }
+    user_name::insert_many(db, user.id, names).await
+}
+
+async fn record_collections(db: &impl ConnectionTrait, user: &Model) -> anyhow::Result<()> {
+    let Some(collections) = &user.extra.collections else {
+        return Ok(());
+    };
+    user_collection_snapshot::record(db, user.id, collections, user.update_at).await
+}
+
+async fn observe_profile(db: &impl ConnectionTrait, user: &Model) -> anyhow::Result<()> {
+    let Some(profile) = &user.extra.profile else {
+        return Ok(());
+    };
+    user_profile_history::observe(db, user.id, profile, user.update_at).await
 }
 
 pub async fn upsert_user(db: &impl ConnectionTrait, init: InitUser) -> anyhow::Result<Model> {
@@ -101,11 +178,119 @@ pub async fn upsert_user(db: &impl ConnectionTrait, init: InitUser) -> anyhow::R
             .add(Column::Nid.eq(nid))
             .add(Column::Sid.eq(sid)),
     };
-    if let Some(existing) = Entity::find().filter(cond).one(db).await? {
-        update_user(db, init, existing).await
+    // a nid row and a sid row of the same user when both were looked up
+    // before either was known to be the other; locked so that `extra`
+    // written in between, such as the friends, is not overwritten
+    let mut found = Entity::find().filter(cond).lock_exclusive().all(db).await?;
+    let existing = match (found.pop(), found.pop()) {
+        (None, _) => None,
+        (Some(user), None) => Some(user),
+        (Some(a), Some(b)) => {
+            let (keep, dup) = if a.nid.is_some() && a.nid == init.nid {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            Some(merge(db, keep, dup).await?)
+        }
+    };
+    match existing {
+        Some(existing) => update_user(db, init, existing).await,
+        None => insert_user(db, init).await,
+    }
+}
+
+/// Give `user` a new sid, remembering the old one as an alias. A row that
+/// already holds `sid` is the same user looked up under the new sid and is
+/// merged into `user`.
+pub async fn rename_sid(db: &impl ConnectionTrait, user: Model, sid: Sid) -> anyhow::Result<Model> {
+    if user.sid.as_ref() == Some(&sid) {
+        return Ok(user);
+    }
+    let holder = Entity::find()
+        .filter(Column::Sid.eq(sid.clone()))
+        .one(db)
+        .await?;
+    let user = match holder {
+        Some(holder) if holder.id != user.id => merge(db, user, holder).await?,
+        _ => user,
+    };
+    if user.sid.as_ref() == Some(&sid) {
+        user_sid_alias::remove(db, &sid).await?;
+        return Ok(user);
+    }
+    if let Some(old) = user.sid.clone() {
+        user_sid_alias::record(db, old, user.id, chrono::Utc::now()).await?;
+    }
+    user_sid_alias::remove(db, &sid).await?;
+    let mut am: ActiveModel = user.into();
+    am.sid = Set(Some(sid));
+    let user = am.update(db).await?;
+    Ok(user)
+}
+
+/// Fold `dup` into `keep` and delete it. Names, collection snapshots and
+/// lists, sid aliases, state logs, profile history and friends move to
+/// `keep`; the profile of the more recently updated row wins and both name
+/// histories are combined.
+pub async fn merge(db: &impl ConnectionTrait, keep: Model, dup: Model) -> anyhow::Result<Model> {
+    tracing::info!(
+        "Merging user {} ({:?}, {:?}) into {} ({:?}, {:?})",
+        dup.id,
+        dup.nid,
+        dup.sid,
+        keep.id,
+        keep.nid,
+        keep.sid
+    );
+    user_name::reassign(db, dup.id, keep.id).await?;
+    user_collection_snapshot::reassign(db, dup.id, keep.id).await?;
+    user_sid_alias::reassign(db, dup.id, keep.id).await?;
+    user_state_log::reassign(db, dup.id, keep.id).await?;
+    user_profile_history::reassign(db, dup.id, keep.id).await?;
+    user_collection::reassign(db, dup.id, keep.id).await?;
+    user_friend::reassign(db, dup.id, keep.id).await?;
+    // free the unique nid and sid of `dup` before `keep` takes them
+    Entity::delete_by_id(dup.id).exec(db).await?;
+
+    let (newer, older) = if dup.update_at > keep.update_at {
+        (dup, keep.clone())
     } else {
-        insert_user(db, init).await
+        (keep.clone(), dup)
+    };
+    let sid = newer.sid.clone().or(older.sid.clone());
+    if let Some(old) = [&newer.sid, &older.sid]
+        .into_iter()
+        .flatten()
+        .find(|s| Some(*s) != sid.as_ref())
+    {
+        user_sid_alias::record(db, old.clone(), keep.id, chrono::Utc::now()).await?;
     }
+    let name_history = match (newer.extra.name_history, older.extra.name_history) {
+        (Some(mut nh), Some(other)) => {
+            merge_names(&mut nh.names, other.names);
+            Some(nh)
+        }
+        (nh, other) => nh.or(other),
+    };
+    let extra = Extra {
+        name_history,
+        collections: newer.extra.collections.or(older.extra.collections),
+        profile: newer.extra.profile.or(older.extra.profile),
+        friends: newer.extra.friends.or(older.extra.friends),
+    };
+    let mut am: ActiveModel = keep.into();
+    am.nid = Set(newer.nid.or(older.nid));
+    am.sid = Set(sid);
+    am.name = Set(newer.name);
+    am.state = Set(newer.state);
+    am.join_time = Set(newer.join_time.or(older.join_time));
+    am.last_active = Set(newer.last_active.or(older.last_active));
+    am.update_at = Set(newer.update_at);
+    am.extra = Set(extra);
+    let user = am.update(db).await?;
+    insert_names(db, &user).await?;
+    Ok(user)
 }
 
 pub async fn update_name_history(
@@ -115,10 +300,278 @@ pub async fn update_name_history(
 ) -> anyhow::Result<Model> {
     let user = find_by_uid(db, uid).await?;
     let user = user.ok_or(anyhow::anyhow!("User not found"))?;
+    // read again under a lock, see `upsert_user`
+    let user = Entity::find_by_id(user.id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or(anyhow::anyhow!("User not found"))?;
     let mut extra = user.extra.clone();
     let extra = extra.update_name_history(names_update).to_owned();
     let mut user: ActiveModel = user.into();
     user.extra = Set(extra);
     user.clone().save(db).await?;
-    Ok(user.try_into_model()?)
+    let user = user.try_into_model()?;
+    insert_names(db, &user).await?;
+    Ok(user)
+}
+
+/// Store the friends of user `id` scraped at `at` and remember when. Only
+/// `extra.friends` is written, so concurrent profile or name history
+/// updates of the same row are kept.
+pub async fn update_friends(
+    db: &impl ConnectionTrait,
+    id: Uuid,
+    friends: &[Friend],
+    at: DateTimeUtc,
+) -> anyhow::Result<Model> {
+    user_friend::replace(db, id, friends, at).await?;
+    let list = FriendList {
+        count: friends.len(),
+        update_at: at,
+    };
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"UPDATE "user" SET "extra" = jsonb_set("extra", '{friends}', CAST($2 AS jsonb))
+        WHERE "id" = $1"#,
+        [id.into(), serde_json::to_string(&list)?.into()],
+    );
+    db.execute(stmt).await?;
+    let user = Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or(anyhow::anyhow!("User not found"))?;
+    Ok(user)
+}
+
+/// Users whose friends page was never scraped or was scraped before
+/// `cutoff`, the stalest first.
+pub async fn find_friends_expired(
+    db: &impl ConnectionTrait,
+    cutoff: DateTimeUtc,
+    limit: u64,
+) -> anyhow::Result<Vec<Model>> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT * FROM "user"
+        WHERE "extra"->'friends' IS NULL
+        OR CAST("extra"->'friends'->>'update_at' AS timestamptz) < $1
+        ORDER BY CAST("extra"->'friends'->>'update_at' AS timestamptz) ASC NULLS FIRST, "id"
+        LIMIT $2"#,
+        [cutoff.into(), (limit as i64).into()],
+    );
+    let users = Entity::find().from_raw_sql(stmt).all(db).await?;
+    Ok(users)
+}
+
+/// Users last updated before the cutoff of their state, ordered by
+/// `(update_at, id)` and starting after `after`, for walking in batches.
+pub async fn find_expired(
+    db: &impl ConnectionTrait,
+    cutoffs: &[(UserState, DateTimeUtc)],
+    after: Option<(DateTimeUtc, Uuid)>,
+    limit: u64,
+) -> anyhow::Result<Vec<Model>> {
+    let expired = cutoffs
+        .iter()
+        .fold(Condition::any(), |cond, (state, cutoff)| {
+            cond.add(
+                Condition::all()
+                    .add(Column::State.eq(state.clone()))
+                    .add(Column::UpdateAt.lt(*cutoff)),
+            )
+        });
+    let mut query = Entity::find().filter(expired);
+    if let Some((update_at, id)) = after {
+        query = query.filter(
+            Condition::any().add(Column::UpdateAt.gt(update_at)).add(
+                Condition::all()
+                    .add(Column::UpdateAt.eq(update_at))
+                    .add(Column::Id.gt(id)),
+            ),
+        );
+    }
+    let users = query
+        .order_by_asc(Column::UpdateAt)
+        .order_by_asc(Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+    Ok(users)
+}
+
+/// Users matching `uids` by nid, sid or former sid, in the order of `uids`.
+/// Unknown uids are left out.
+pub async fn find_by_uids(
+    db: &impl ConnectionTrait,
+    uids: &[Uid],
+) -> anyhow::Result<Vec<(Uid, Model)>> {
+    let (mut nids, mut sids) = (Vec::new(), Vec::new());
+    for uid in uids {
+        match uid {
+            Uid::Nid(nid) => nids.push(*nid),
+            Uid::Sid(sid) => sids.push(sid.clone()),
+        }
+    }
+    if nids.is_empty() && sids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let cond = Condition::any()
+        .add(Column::Nid.is_in(nids))
+        .add(Column::Sid.is_in(sids.clone()));
+    let mut users = Entity::find().filter(cond).all(db).await?;
+    sids.retain(|sid| !users.iter().any(|u| u.sid.as_ref() == Some(sid)));
+    let aliases = user_sid_alias::find_many(db, sids).await?;
+    let ids = aliases.iter().map(|a| a.user_id).collect::<Vec<_>>();
+    if !ids.is_empty() {
+        users.extend(find_by_ids(db, ids).await?);
+    }
+    let found = uids
+        .iter()
+        .filter_map(|uid| {
+            let user = users.iter().find(|u| match uid {
+                Uid::Nid(nid) => u.nid == Some(*nid),
+                Uid::Sid(sid) => {
+                    u.sid.as_ref() == Some(sid)
+                        || aliases.iter().any(|a| &a.sid == sid && a.user_id == u.id)
+                }
+            })?;
+            Some((uid.clone(), user.clone()))
+        })
+        .collect();
+    Ok(found)
+}
+
+pub async fn find_by_ids(db: &impl ConnectionTrait, ids: Vec<Uuid>) -> anyhow::Result<Vec<Model>> {
+    let users = Entity::find().filter(Column::Id.is_in(ids)).all(db).await?;
+    Ok(users)
+}
+
+/// Filter over the user listing.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub states: Vec<UserState>,
+    pub joined_after: Option<DateTimeUtc>,
+    pub joined_before: Option<DateTimeUtc>,
+    pub active_since: Option<DateTimeUtc>,
+    pub active_before: Option<DateTimeUtc>,
+    pub name_prefix: Option<String>,
+    pub sort: UserSort,
+    pub desc: bool,
+    pub after: Option<(DateTimeUtc, Uuid)>,
+}
+
+impl Filter {
+    /// Any of `states`, all states when empty.
+    pub fn set_states(&mut self, states: Vec<UserState>) -> &mut Self {
+        self.states = states;
+        self
+    }
+
+    pub fn set_joined(
+        &mut self,
+        after: Option<DateTimeUtc>,
+        before: Option<DateTimeUtc>,
+    ) -> &mut Self {
+        self.joined_after = after;
+        self.joined_before = before;
+        self
+    }
+
+    pub fn set_active(
+        &mut self,
+        since: Option<DateTimeUtc>,
+        before: Option<DateTimeUtc>,
+    ) -> &mut Self {
+        self.active_since = since;
+        self.active_before = before;
+        self
+    }
+
+    /// Current names starting with `prefix`, ignoring case.
+    pub fn set_name_prefix(&mut self, prefix: Option<String>) -> &mut Self {
+        self.name_prefix = prefix.filter(|p| !p.is_empty());
+        self
+    }
+
+    /// Users without a value in the sort column are left out.
+    pub fn set_sort(&mut self, sort: UserSort, desc: bool) -> &mut Self {
+        self.sort = sort;
+        self.desc = desc;
+        self
+    }
+
+    /// Only users ordered after the sort key and id of the last user of the
+    /// previous page.
+    pub fn set_after(&mut self, after: Option<(DateTimeUtc, Uuid)>) -> &mut Self {
+        self.after = after;
+        self
+    }
+
+    fn sort_column(&self) -> Column {
+        match self.sort {
+            UserSort::UpdateAt => Column::UpdateAt,
+            UserSort::JoinTime => Column::JoinTime,
+            UserSort::LastActive => Column::LastActive,
+        }
+    }
+
+    pub fn condition(&self) -> Condition {
+        let mut cond = Condition::all();
+        if !self.states.is_empty() {
+            cond = cond.add(Column::State.is_in(self.states.clone()));
+        }
+        if let Some(after) = self.joined_after {
+            cond = cond.add(Column::JoinTime.gte(after));
+        }
+        if let Some(before) = self.joined_before {
+            cond = cond.add(Column::JoinTime.lt(before));
+        }
+        if let Some(since) = self.active_since {
+            cond = cond.add(Column::LastActive.gte(since));
+        }
+        if let Some(before) = self.active_before {
+            cond = cond.add(Column::LastActive.lt(before));
+        }
+        if let Some(prefix) = &self.name_prefix {
+            let pattern = format!("{}%", escape_like(&prefix.to_lowercase()));
+            cond = cond.add(Expr::expr(Func::lower(Expr::col(Column::Name))).like(pattern));
+        }
+        let column = self.sort_column();
+        cond = cond.add(column.is_not_null());
+        if let Some((key, id)) = self.after {
+            let (past, id_past) = if self.desc {
+                (column.lt(key), Column::Id.lt(id))
+            } else {
+                (column.gt(key), Column::Id.gt(id))
+            };
+            cond = cond.add(
+                Condition::any()
+                    .add(past)
+                    .add(Condition::all().add(column.eq(key)).add(id_past)),
+            );
+        }
+        cond
+    }
+}
+
+/// One page of users matching `filter`, at most `limit`.
+pub async fn find_by_filter(
+    db: &impl ConnectionTrait,
+    filter: &Filter,
+    limit: u64,
+) -> anyhow::Result<Vec<Model>> {
+    let order = if filter.desc {
+        db::Order::Desc
+    } else {
+        db::Order::Asc
+    };
+    let users = Entity::find()
+        .filter(filter.condition())
+        .order_by(filter.sort_column(), order.clone())
+        .order_by(Column::Id, order)
+        .limit(limit)
+        .all(db)
+        .await?;
+    Ok(users)
 }
@@ -0,0 +1,157 @@
+use db::prelude::*;
+use db::sea_query::OnConflict;
+use db::{QueryOrder, Statement};
+use model::common::user::{CollectionItem, SubjectState, SubjectType};
+use model::entity::user_collection::{ActiveModel, Column, Entity, Model};
+use model::entity::user_collection_list;
+
+const CHUNK_SIZE: usize = 500;
+
+/// Store a scraped collection list. Subjects moved here from another list
+/// of the type are updated. When the list is `complete`, subjects no longer
+/// on it are dropped; a partial list never removes anything.
+pub async fn replace_list(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    subject_type: SubjectType,
+    state: SubjectState,
+    items: &[CollectionItem],
+    complete: bool,
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    let models = items
+        .iter()
+        .map(|item| ActiveModel {
+            user_id: db::Set(user_id),
+            subject_type: db::Set(subject_type),
+            subject_id: db::Set(item.subject_id),
+            state: db::Set(state),
+            title: db::Set(item.title.clone()),
+            subtitle: db::Set(item.subtitle.clone()),
+            rating: db::Set(item.rating),
+            tags: db::Set(item.tags.clone()),
+            comment: db::Set(item.comment.clone()),
+            marked_at: db::Set(item.marked_at),
+            update_at: db::Set(at),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for chunk in models.chunks(CHUNK_SIZE) {
+        Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([Column::UserId, Column::SubjectType, Column::SubjectId])
+                    .update_columns([
+                        Column::State,
+                        Column::Title,
+                        Column::Subtitle,
+                        Column::Rating,
+                        Column::Tags,
+                        Column::Comment,
+                        Column::MarkedAt,
+                        Column::UpdateAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+    if complete {
+        let ids = items.iter().map(|item| item.subject_id).collect::<Vec<_>>();
+        Entity::delete_many()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::SubjectType.eq(subject_type))
+            .filter(Column::State.eq(state))
+            .filter(Column::SubjectId.is_not_in(ids))
+            .exec(db)
+            .await?;
+    }
+    let list = user_collection_list::ActiveModel {
+        user_id: db::Set(user_id),
+        subject_type: db::Set(subject_type),
+        state: db::Set(state),
+        synced_at: db::Set(at),
+        ..Default::default()
+    };
+    user_collection_list::Entity::insert(list)
+        .on_conflict(
+            OnConflict::columns([
+                user_collection_list::Column::UserId,
+                user_collection_list::Column::SubjectType,
+                user_collection_list::Column::State,
+            ])
+            .update_column(user_collection_list::Column::SyncedAt)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// When the list was last scraped, `None` when it never was.
+pub async fn synced_at(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    subject_type: SubjectType,
+    state: SubjectState,
+) -> anyhow::Result<Option<DateTimeUtc>> {
+    let list = user_collection_list::Entity::find()
+        .filter(user_collection_list::Column::UserId.eq(user_id))
+        .filter(user_collection_list::Column::SubjectType.eq(subject_type))
+        .filter(user_collection_list::Column::State.eq(state))
+        .one(db)
+        .await?;
+    Ok(list.map(|list| list.synced_at))
+}
+
+/// Subjects on a list of `user_id`, or on every list of the type when
+/// `state` is `None`, most recently marked first.
+pub async fn find_list(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    subject_type: SubjectType,
+    state: Option<SubjectState>,
+) -> anyhow::Result<Vec<Model>> {
+    let mut query = Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::SubjectType.eq(subject_type));
+    if let Some(state) = state {
+        query = query.filter(Column::State.eq(state));
+    }
+    let items = query
+        .order_by_desc(Column::MarkedAt)
+        .order_by_desc(Column::Id)
+        .all(db)
+        .await?;
+    Ok(items)
+}
+
+/// Move the collections of `from` to `to`. Subjects and lists `to` already
+/// has are left behind and go away with `from`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    for sql in [
+        r#"UPDATE "user_collection" AS c SET "user_id" = $2
+        WHERE c."user_id" = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_collection" AS o
+            WHERE o."user_id" = $2
+            AND o."subject_type" = c."subject_type"
+            AND o."subject_id" = c."subject_id"
+        )"#,
+        r#"UPDATE "user_collection_list" AS l SET "user_id" = $2
+        WHERE l."user_id" = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_collection_list" AS o
+            WHERE o."user_id" = $2
+            AND o."subject_type" = l."subject_type"
+            AND o."state" = l."state"
+        )"#,
+    ] {
+        let stmt = Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            [from.into(), to.into()],
+        );
+        db.execute(stmt).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use db::QueryOrder;
+use db::prelude::*;
+use db::sea_query::Expr;
+use model::common::user::Collections;
+use model::entity::user_collection_snapshot::{ActiveModel, Column, Entity, Model};
+
+async fn latest_before(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    before: Option<DateTimeUtc>,
+) -> anyhow::Result<Option<Model>> {
+    let mut query = Entity::find().filter(Column::UserId.eq(user_id));
+    if let Some(before) = before {
+        query = query.filter(Column::CreatedAt.lt(before));
+    }
+    let snapshot = query
+        .order_by_desc(Column::CreatedAt)
+        .order_by_desc(Column::Id)
+        .one(db)
+        .await?;
+    Ok(snapshot)
+}
+
+/// Append `collections` to the series of `user_id` unless they equal the
+/// latest snapshot.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    collections: &Collections,
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    let latest = latest_before(db, user_id, None).await?;
+    if latest.is_some_and(|s| &s.collections == collections) {
+        return Ok(());
+    }
+    let snapshot = ActiveModel {
+        user_id: db::Set(user_id),
+        collections: db::Set(collections.clone()),
+        created_at: db::Set(at),
+        ..Default::default()
+    };
+    Entity::insert(snapshot).exec(db).await?;
+    Ok(())
+}
+
+/// Snapshots of `user_id` in `[from, to)`, oldest first. The last snapshot
+/// before `from` comes first, as the counts in effect at `from`.
+pub async fn find_between(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    from: Option<DateTimeUtc>,
+    to: Option<DateTimeUtc>,
+) -> anyhow::Result<Vec<Model>> {
+    let mut query = Entity::find().filter(Column::UserId.eq(user_id));
+    if let Some(from) = from {
+        query = query.filter(Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = to {
+        query = query.filter(Column::CreatedAt.lt(to));
+    }
+    let mut snapshots = Vec::new();
+    if from.is_some() {
+        snapshots.extend(latest_before(db, user_id, from).await?);
+    }
+    snapshots.extend(
+        query
+            .order_by_asc(Column::CreatedAt)
+            .order_by_asc(Column::Id)
+            .all(db)
+            .await?,
+    );
+    Ok(snapshots)
+}
+
+/// Move the series of `from` to `to`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    Entity::update_many()
+        .col_expr(Column::UserId, Expr::value(to))
+        .filter(Column::UserId.eq(from))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use db::prelude::*;
+use db::sea_query::OnConflict;
+use db::{QueryOrder, Statement};
+use model::common::user::{Friend, Uid};
+use model::entity::user_friend::{ActiveModel, Column, Entity, Model};
+
+const CHUNK_SIZE: usize = 500;
+
+/// Store the friends page of a user scraped in full. Friends no longer
+/// listed are dropped, the others keep when they were first seen.
+pub async fn replace(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    friends: &[Friend],
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    let models = friends
+        .iter()
+        .map(|friend| ActiveModel {
+            user_id: db::Set(user_id),
+            friend_uid: db::Set(friend.uid.to_string()),
+            friend_nid: db::Set(match friend.uid {
+                Uid::Nid(nid) => Some(nid),
+                Uid::Sid(_) => None,
+            }),
+            name: db::Set(friend.name.clone()),
+            first_seen: db::Set(at),
+            last_seen: db::Set(at),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for chunk in models.chunks(CHUNK_SIZE) {
+        Entity::insert_many(chunk.to_vec())
+            .on_conflict(
+                OnConflict::columns([Column::UserId, Column::FriendUid])
+                    .update_columns([Column::FriendNid, Column::Name, Column::LastSeen])
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+    let uids = friends
+        .iter()
+        .map(|friend| friend.uid.to_string())
+        .collect::<Vec<_>>();
+    Entity::delete_many()
+        .filter(Column::UserId.eq(user_id))
+        .filter(Column::FriendUid.is_not_in(uids))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn find_by_user(db: &impl ConnectionTrait, user_id: Uuid) -> anyhow::Result<Vec<Model>> {
+    let friends = Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .order_by_asc(Column::FirstSeen)
+        .order_by_asc(Column::Id)
+        .all(db)
+        .await?;
+    Ok(friends)
+}
+
+/// Uids among the friends of a user whose own friends page lists the user
+/// back. Only friends that are tracked users can be checked.
+pub async fn find_mutual(db: &impl ConnectionTrait, user_id: Uuid) -> anyhow::Result<Vec<String>> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT DISTINCT f."friend_uid"
+        FROM "user_friend" AS f
+        JOIN "user" AS u ON u."id" = f."user_id"
+        JOIN "user" AS r ON r."sid" = f."friend_uid" OR r."nid" = f."friend_nid"
+        WHERE f."user_id" = $1
+        AND EXISTS (
+            SELECT 1 FROM "user_friend" AS g
+            WHERE g."user_id" = r."id"
+            AND (g."friend_uid" = u."sid" OR g."friend_nid" = u."nid")
+        )"#,
+        [user_id.into()],
+    );
+    let mut uids = Vec::new();
+    for row in db.query_all(stmt).await? {
+        uids.push(row.try_get("", "friend_uid")?);
+    }
+    Ok(uids)
+}
+
+/// A friend of friends who is not a friend yet.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub uid: String,
+    pub name: String,
+    /// Friends of the user who list this one.
+    pub common: i64,
+}
+
+/// Friends of the tracked friends of a user, most common friends first.
+pub async fn find_suggestions(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    limit: u64,
+) -> anyhow::Result<Vec<Suggestion>> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT g."friend_uid" AS uid, MAX(g."name") AS name, COUNT(*) AS common
+        FROM "user_friend" AS f
+        JOIN "user" AS u ON u."id" = f."user_id"
+        JOIN "user" AS r ON r."sid" = f."friend_uid" OR r."nid" = f."friend_nid"
+        JOIN "user_friend" AS g ON g."user_id" = r."id"
+        WHERE f."user_id" = $1
+        AND g."friend_uid" IS DISTINCT FROM u."sid"
+        AND g."friend_nid" IS DISTINCT FROM u."nid"
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_friend" AS o
+            WHERE o."user_id" = $1 AND o."friend_uid" = g."friend_uid"
+        )
+        GROUP BY g."friend_uid"
+        ORDER BY common DESC, uid
+        LIMIT $2"#,
+        [user_id.into(), (limit as i64).into()],
+    );
+    let mut suggestions = Vec::new();
+    for row in db.query_all(stmt).await? {
+        suggestions.push(Suggestion {
+            uid: row.try_get("", "uid")?,
+            name: row.try_get("", "name")?,
+            common: row.try_get("", "common")?,
+        });
+    }
+    Ok(suggestions)
+}
+
+/// Uids listed as friends that are neither a tracked user nor a former sid
+/// of one, most listed first.
+pub async fn find_undiscovered(db: &impl ConnectionTrait, limit: u64) -> anyhow::Result<Vec<Uid>> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT f."friend_uid"
+        FROM "user_friend" AS f
+        WHERE NOT EXISTS (
+            SELECT 1 FROM "user" AS r
+            WHERE r."sid" = f."friend_uid" OR r."nid" = f."friend_nid"
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_sid_alias" AS a WHERE a."sid" = f."friend_uid"
+        )
+        GROUP BY f."friend_uid"
+        ORDER BY COUNT(*) DESC, f."friend_uid"
+        LIMIT $1"#,
+        [(limit as i64).into()],
+    );
+    let mut uids = Vec::new();
+    for row in db.query_all(stmt).await? {
+        let uid: String = row.try_get("", "friend_uid")?;
+        uids.push(Uid::from_str(&uid));
+    }
+    Ok(uids)
+}
+
+/// Move the friends of `from` to `to`. Friends `to` already lists are left
+/// behind and go away with `from`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"UPDATE "user_friend" AS f SET "user_id" = $2
+        WHERE f."user_id" = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_friend" AS o
+            WHERE o."user_id" = $2 AND o."friend_uid" = f."friend_uid"
+        )"#,
+        [from.into(), to.into()],
+    );
+    db.execute(stmt).await?;
+    Ok(())
+}
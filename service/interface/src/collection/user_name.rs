@@ -0,0 +1,121 @@
+use db::Statement;
+use db::prelude::*;
+use db::sea_query::OnConflict;
+use model::common::text::{MatchKind, escape_like, normalize};
+use model::entity::user_name::{ActiveModel, Column, Entity};
+
+/// Remember `names` for `user_id`, names already known are skipped.
+pub async fn insert_many<'a>(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    names: impl IntoIterator<Item = &'a String>,
+) -> anyhow::Result<()> {
+    let models = names
+        .into_iter()
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| ActiveModel {
+            user_id: db::Set(user_id),
+            name: db::Set(name.clone()),
+            normalized: db::Set(normalize(name)),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    if models.is_empty() {
+        return Ok(());
+    }
+    Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([Column::UserId, Column::Name])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+/// Move the names of `from` to `to`. Names `to` already has are left behind
+/// and go away with `from`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    let sql = r#"UPDATE "user_name" AS n SET "user_id" = $2
+        WHERE n."user_id" = $1
+        AND NOT EXISTS (
+            SELECT 1 FROM "user_name" AS o WHERE o."user_id" = $2 AND o."name" = n."name"
+        )"#;
+    let stmt =
+        Statement::from_sql_and_values(db.get_database_backend(), sql, [from.into(), to.into()]);
+    db.execute(stmt).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub user_id: Uuid,
+    pub name: String,
+    pub kind: MatchKind,
+    /// Trigram similarity to the query, in `[0, 1]`.
+    pub score: f32,
+}
+
+/// Names equal to `name` once normalized, exact spellings first. With
+/// `fuzzy`, prefix, substring and trigram matches follow.
+pub async fn search(
+    db: &impl ConnectionTrait,
+    name: &str,
+    fuzzy: bool,
+    limit: u64,
+) -> anyhow::Result<Vec<Hit>> {
+    let q = normalize(name);
+    if q.is_empty() {
+        return Ok(Vec::new());
+    }
+    let escaped = escape_like(&q);
+    let filter = if fuzzy {
+        r#""normalized" = $2 OR "normalized" LIKE $4 OR "normalized" % $2"#
+    } else {
+        r#""normalized" = $2"#
+    };
+    let sql = format!(
+        r#"SELECT "user_id", "name",
+            CASE
+                WHEN "name" = $1 THEN 4
+                WHEN "normalized" = $2 THEN 3
+                WHEN "normalized" LIKE $3 THEN 2
+                WHEN "normalized" LIKE $4 THEN 1
+                ELSE 0
+            END AS "rank",
+            similarity("normalized", $2) AS "score"
+        FROM "user_name"
+        WHERE {filter}
+        ORDER BY "rank" DESC, "score" DESC, "user_id", "name"
+        LIMIT $5"#
+    );
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        sql,
+        [
+            name.trim().into(),
+            q.clone().into(),
+            format!("{escaped}%").into(),
+            format!("%{escaped}%").into(),
+            (limit as i64).into(),
+        ],
+    );
+    let mut hits = Vec::new();
+    for row in db.query_all(stmt).await? {
+        let rank: i32 = row.try_get("", "rank")?;
+        hits.push(Hit {
+            user_id: row.try_get("", "user_id")?,
+            name: row.try_get("", "name")?,
+            kind: match rank {
+                4 => MatchKind::Exact,
+                3 => MatchKind::Normalized,
+                2 => MatchKind::Prefix,
+                1 => MatchKind::Contains,
+                _ => MatchKind::Fuzzy,
+            },
+            score: row.try_get("", "score")?,
+        });
+    }
+    Ok(hits)
+}
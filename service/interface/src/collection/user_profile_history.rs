@@ -0,0 +1,73 @@
+use db::prelude::*;
+use db::sea_query::Expr;
+use db::{ActiveModelTrait, QueryOrder};
+use model::common::user::{Profile, ProfileField};
+use model::entity::user_profile_history::{ActiveModel, Column, Entity, Model};
+
+/// Record that `user_id` had `profile` at `at`. A field still holding the
+/// value it was last seen with only has its `last_seen` moved forward.
+pub async fn observe(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    profile: &Profile,
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    for (field, value) in profile.fields() {
+        let latest = Entity::find()
+            .filter(Column::UserId.eq(user_id))
+            .filter(Column::Field.eq(field))
+            .order_by_desc(Column::LastSeen)
+            .order_by_desc(Column::Id)
+            .one(db)
+            .await?;
+        match latest {
+            Some(latest) if latest.value == value => {
+                let mut am: ActiveModel = latest.into();
+                am.last_seen = db::Set(at);
+                am.update(db).await?;
+            }
+            // nothing to remember about a field that was never set
+            None if value.is_none() => {}
+            _ => {
+                let record = ActiveModel {
+                    user_id: db::Set(user_id),
+                    field: db::Set(field),
+                    value: db::Set(value),
+                    first_seen: db::Set(at),
+                    last_seen: db::Set(at),
+                    ..Default::default()
+                };
+                Entity::insert(record).exec(db).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Values of `field`, or of every field, oldest first.
+pub async fn find_by_user(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    field: Option<ProfileField>,
+) -> anyhow::Result<Vec<Model>> {
+    let mut query = Entity::find().filter(Column::UserId.eq(user_id));
+    if let Some(field) = field {
+        query = query.filter(Column::Field.eq(field));
+    }
+    let records = query
+        .order_by_asc(Column::FirstSeen)
+        .order_by_asc(Column::Id)
+        .all(db)
+        .await?;
+    Ok(records)
+}
+
+/// Move the history of `from` to `to`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    Entity::update_many()
+        .col_expr(Column::UserId, Expr::value(to))
+        .filter(Column::UserId.eq(from))
+        .exec(db)
+        .await?;
+    Ok(())
+}
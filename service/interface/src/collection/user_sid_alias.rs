@@ -0,0 +1,55 @@
+use db::prelude::*;
+use db::sea_query::{Expr, OnConflict};
+use model::entity::user::Sid;
+use model::entity::user_sid_alias::{ActiveModel, Column, Entity, Model};
+
+/// Remember that `sid` used to belong to `user_id`. A sid taken over by
+/// another user later points at the latest one.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    sid: Sid,
+    user_id: Uuid,
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    let alias = ActiveModel {
+        sid: db::Set(sid),
+        user_id: db::Set(user_id),
+        created_at: db::Set(at),
+    };
+    Entity::insert(alias)
+        .on_conflict(
+            OnConflict::column(Column::Sid)
+                .update_columns([Column::UserId, Column::CreatedAt])
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn find_many(db: &impl ConnectionTrait, sids: Vec<Sid>) -> anyhow::Result<Vec<Model>> {
+    if sids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let aliases = Entity::find()
+        .filter(Column::Sid.is_in(sids))
+        .all(db)
+        .await?;
+    Ok(aliases)
+}
+
+/// Forget `sid` as an alias, once a user holds it again.
+pub async fn remove(db: &impl ConnectionTrait, sid: &str) -> anyhow::Result<()> {
+    Entity::delete_by_id(sid.to_string()).exec(db).await?;
+    Ok(())
+}
+
+/// Move the aliases of `from` to `to`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    Entity::update_many()
+        .col_expr(Column::UserId, Expr::value(to))
+        .filter(Column::UserId.eq(from))
+        .exec(db)
+        .await?;
+    Ok(())
+}
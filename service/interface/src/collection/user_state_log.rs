@@ -0,0 +1,91 @@
+use db::prelude::*;
+use db::sea_query::Expr;
+use db::{QueryOrder, Statement};
+use model::common::user::UserState;
+use model::entity::user_state_log::{ActiveModel, Column, Entity, Model};
+use std::str::FromStr;
+
+/// Log that `user_id` was seen in state `to`, coming from `from`.
+pub async fn record(
+    db: &impl ConnectionTrait,
+    user_id: Uuid,
+    from: Option<UserState>,
+    to: UserState,
+    at: DateTimeUtc,
+    source: Option<String>,
+) -> anyhow::Result<()> {
+    let log = ActiveModel {
+        user_id: db::Set(user_id),
+        from_state: db::Set(from),
+        to_state: db::Set(to),
+        observed_at: db::Set(at),
+        source: db::Set(source),
+        ..Default::default()
+    };
+    Entity::insert(log).exec(db).await?;
+    Ok(())
+}
+
+/// Every logged state of `user_id`, oldest first.
+pub async fn find_by_user(db: &impl ConnectionTrait, user_id: Uuid) -> anyhow::Result<Vec<Model>> {
+    let logs = Entity::find()
+        .filter(Column::UserId.eq(user_id))
+        .order_by_asc(Column::ObservedAt)
+        .order_by_asc(Column::Id)
+        .all(db)
+        .await?;
+    Ok(logs)
+}
+
+/// Move the log of `from` to `to`.
+pub async fn reassign(db: &impl ConnectionTrait, from: Uuid, to: Uuid) -> anyhow::Result<()> {
+    Entity::update_many()
+        .col_expr(Column::UserId, Expr::value(to))
+        .filter(Column::UserId.eq(from))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayCount {
+    pub day: chrono::NaiveDate,
+    pub state: UserState,
+    pub count: i64,
+}
+
+/// Transitions into each state per day in `[from, to)`, first sightings
+/// excluded. Days start at midnight `offset_secs` east of UTC.
+pub async fn count_by_day(
+    db: &impl ConnectionTrait,
+    from: DateTimeUtc,
+    to: DateTimeUtc,
+    offset_secs: i32,
+) -> anyhow::Result<Vec<DayCount>> {
+    let stmt = Statement::from_sql_and_values(
+        db.get_database_backend(),
+        r#"SELECT
+            (("observed_at" AT TIME ZONE 'UTC') + make_interval(secs => $3))::date AS "day",
+            "to_state",
+            count(*) AS "count"
+        FROM "user_state_log"
+        WHERE "from_state" IS NOT NULL AND "observed_at" >= $1 AND "observed_at" < $2
+        GROUP BY 1, 2
+        ORDER BY 1, 2"#,
+        [from.into(), to.into(), (offset_secs as f64).into()],
+    );
+    let mut counts = Vec::new();
+    for row in db.query_all(stmt).await? {
+        let state: String = row.try_get("", "to_state")?;
+        let Ok(state) = UserState::from_str(&state) else {
+            tracing::warn!("Unknown user state in log: {state}");
+            continue;
+        };
+        counts.push(DayCount {
+            day: row.try_get("", "day")?,
+            state,
+            count: row.try_get("", "count")?,
+        });
+    }
+    Ok(counts)
+}
@@ -1,19 +1,67 @@
 use crate::collection;
 use db::TransactionTrait;
-use model::common::onair::{BangumiItemMap, SubjectIds};
-pub async fn diff_hash(hash: &str) -> anyhow::Result<bool> {
-    collection::kv::OnAir::get(db::get_db())
-        .await
-        .map(|v| v.diff_hash(&hash))
+use model::common::onair::{BangumiItemMap, SiteMetaMap, SubjectId, SubjectIds};
+use model::prelude::{DateTimeUtc, OnAir, OnAirChange};
+use tokio::sync::mpsc;
+
+pub use collection::kv::{OnAir as Record, Validators};
+pub use collection::onair::Filter;
+pub use collection::onair_change::Filter as ChangeFilter;
+pub use collection::onair_title::Hit;
+pub async fn record() -> anyhow::Result<Record> {
+    collection::kv::OnAir::get(db::get_db()).await
+}
+
+/// Store new validators for data that has not changed.
+pub async fn touch(hash: &str, validators: Option<&Validators>) -> anyhow::Result<()> {
+    collection::kv::OnAir::update(db::get_db(), hash, validators).await
 }
 
-pub async fn flush(hash: String, items: BangumiItemMap) -> anyhow::Result<()> {
-    let hash = hash.to_string();
+/// Store a refresh in one transaction. Every batch is recorded in the change
+/// history, indexed and upserted as it arrives. Once `batches` is closed and
+/// `site_meta` resolves, live subjects missing from all batches are
+/// tombstoned. Nothing is recorded in the history on the first import.
+pub async fn flush<F>(
+    hash: String,
+    validators: Option<Validators>,
+    mut batches: mpsc::Receiver<BangumiItemMap>,
+    site_meta: F,
+) -> anyhow::Result<()>
+where
+    F: Future<Output = anyhow::Result<SiteMetaMap>> + Send + 'static,
+{
     db::get_db()
         .transaction::<_, _, anyhow::Error>(|txn| {
             Box::pin(async move {
-                collection::onair::upsert_many(txn, items).await?;
-                collection::kv::OnAir::update(txn, &hash).await?;
+                let now = chrono::Utc::now();
+                let live = collection::onair::find_live_subject_ids(txn).await?;
+                let first_import = live.is_empty();
+                let mut seen = SubjectIds::new();
+                while let Some(items) = batches.recv().await {
+                    if !first_import {
+                        collection::onair_change::record(txn, &items, &SubjectIds::new(), now)
+                            .await?;
+                    }
+                    seen.extend(items.keys());
+                    collection::onair_title::replace_many(txn, &items).await?;
+                    collection::onair::upsert_many(txn, items).await?;
+                    tracing::info!("OnAir flushed {} items", seen.len());
+                }
+                let site_meta = site_meta.await?;
+                if seen.is_empty() {
+                    // never tombstone everything because of a broken download
+                    anyhow::bail!("OnAir data has no items");
+                }
+                let removed = live.difference(&seen).copied().collect::<SubjectIds>();
+                if !removed.is_empty() {
+                    tracing::info!("OnAir items removed: {:?}", removed);
+                    let none = BangumiItemMap::new();
+                    collection::onair_change::record(txn, &none, &removed, now).await?;
+                    collection::onair_title::delete_many(txn, &removed).await?;
+                    collection::onair::mark_removed(txn, &removed, now).await?;
+                }
+                collection::kv::SiteMeta::set(txn, &site_meta).await?;
+                collection::kv::OnAir::update(txn, &hash, validators.as_ref()).await?;
                 Ok(())
             })
         })
@@ -22,6 +70,48 @@ pub async fn flush(hash: String, items: BangumiItemMap) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn find_live_subject_ids() -> anyhow::Result<SubjectIds> {
+    collection::onair::find_live_subject_ids(db::get_db()).await
+}
+
+pub async fn find_models_by_subject_ids(
+    ids: &SubjectIds,
+    include_removed: bool,
+) -> anyhow::Result<Vec<OnAir>> {
+    collection::onair::find_models_by_subject_ids(db::get_db(), ids, include_removed).await
+}
+
 pub async fn find_by_subject_ids(ids: &SubjectIds) -> anyhow::Result<BangumiItemMap> {
     collection::onair::find_by_subject_ids(db::get_db(), ids).await
 }
+
+pub async fn find_by_date_range(
+    from: DateTimeUtc,
+    to: DateTimeUtc,
+) -> anyhow::Result<BangumiItemMap> {
+    collection::onair::find_by_date_range(db::get_db(), from, to).await
+}
+
+pub async fn site_meta() -> anyhow::Result<SiteMetaMap> {
+    collection::kv::SiteMeta::get(db::get_db()).await
+}
+
+pub async fn find_by_filter(
+    filter: &Filter,
+    page: u64,
+    limit: u64,
+) -> anyhow::Result<(Vec<OnAir>, u64)> {
+    collection::onair::find_by_filter(db::get_db(), filter, page, limit).await
+}
+
+pub async fn search(q: &str, limit: u64) -> anyhow::Result<Vec<Hit>> {
+    collection::onair_title::search(db::get_db(), q, limit).await
+}
+
+pub async fn find_changes(filter: &ChangeFilter, limit: u64) -> anyhow::Result<Vec<OnAirChange>> {
+    collection::onair_change::find_by_filter(db::get_db(), filter, limit).await
+}
+
+pub async fn find_history(subject: SubjectId) -> anyhow::Result<Vec<OnAirChange>> {
+    collection::onair_change::find_by_subject(db::get_db(), subject).await
+}
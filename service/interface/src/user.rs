@@ -1,15 +1,202 @@
 use crate::collection;
-use model::common::user::{InitUser, NamesUpdate, Uid};
+use db::TransactionTrait;
+use db::prelude::Uuid;
+use model::common::user::{InitUser, NamesUpdate, Sid, Uid, UserState};
+use model::common::user::{SubjectState, SubjectType};
 use model::entity::user::Model;
+use model::prelude::{
+    CollectionItem, DateTimeUtc, Friend, ProfileField, UserCollection, UserCollectionSnapshot,
+    UserFriend, UserProfileHistory, UserStateLog,
+};
+
+pub use collection::user::Filter;
+pub use collection::user_friend::Suggestion as FriendSuggestion;
+pub use collection::user_name::Hit as NameHit;
+pub use collection::user_state_log::DayCount as StateDayCount;
 
 pub async fn find_by_uid(uid: Uid) -> anyhow::Result<Option<Model>> {
     collection::user::find_by_uid(db::get_db(), uid).await
 }
 
+pub async fn find_by_uids(uids: &[Uid]) -> anyhow::Result<Vec<(Uid, Model)>> {
+    collection::user::find_by_uids(db::get_db(), uids).await
+}
+
+/// Insert or update a user in one transaction, merging the nid row and the
+/// sid row of the same user when both exist.
 pub async fn upsert_user(init: InitUser) -> anyhow::Result<Model> {
-    collection::user::upsert_user(db::get_db(), init).await
+    let user = db::get_db()
+        .transaction::<_, _, anyhow::Error>(|txn| {
+            Box::pin(async move { collection::user::upsert_user(txn, init).await })
+        })
+        .await?;
+    Ok(user)
+}
+
+/// Persist a sid change noticed through a redirect, see
+/// [`collection::user::rename_sid`].
+pub async fn rename_sid(user: Model, sid: Sid) -> anyhow::Result<Model> {
+    let user = db::get_db()
+        .transaction::<_, _, anyhow::Error>(|txn| {
+            Box::pin(async move { collection::user::rename_sid(txn, user, sid).await })
+        })
+        .await?;
+    Ok(user)
 }
 
 pub async fn update_name_history(uid: Uid, update: NamesUpdate) -> anyhow::Result<Model> {
-    collection::user::update_name_history(db::get_db(), uid, update).await
+    let user = db::get_db()
+        .transaction::<_, _, anyhow::Error>(|txn| {
+            Box::pin(async move { collection::user::update_name_history(txn, uid, update).await })
+        })
+        .await?;
+    Ok(user)
+}
+
+pub async fn find_expired(
+    cutoffs: &[(UserState, DateTimeUtc)],
+    after: Option<(DateTimeUtc, Uuid)>,
+    limit: u64,
+) -> anyhow::Result<Vec<Model>> {
+    collection::user::find_expired(db::get_db(), cutoffs, after, limit).await
+}
+
+pub async fn find_by_filter(filter: &Filter, limit: u64) -> anyhow::Result<Vec<Model>> {
+    collection::user::find_by_filter(db::get_db(), filter, limit).await
+}
+
+/// Users who have been called `name`, in the order of their best match.
+pub async fn find_by_name(
+    name: &str,
+    fuzzy: bool,
+    limit: u64,
+) -> anyhow::Result<Vec<(Model, Vec<NameHit>)>> {
+    let db = db::get_db();
+    let hits = collection::user_name::search(db, name, fuzzy, limit).await?;
+    let mut grouped: Vec<(Uuid, Vec<NameHit>)> = Vec::new();
+    for hit in hits {
+        match grouped.iter_mut().find(|(id, _)| *id == hit.user_id) {
+            Some((_, names)) => names.push(hit),
+            None => grouped.push((hit.user_id, vec![hit])),
+        }
+    }
+    let ids = grouped.iter().map(|(id, _)| *id).collect();
+    let mut users = collection::user::find_by_ids(db, ids).await?;
+    let result = grouped
+        .into_iter()
+        .filter_map(|(id, hits)| {
+            let i = users.iter().position(|u| u.id == id)?;
+            Some((users.swap_remove(i), hits))
+        })
+        .collect();
+    Ok(result)
+}
+
+pub async fn find_collection_history(
+    user: &Model,
+    from: Option<DateTimeUtc>,
+    to: Option<DateTimeUtc>,
+) -> anyhow::Result<Vec<UserCollectionSnapshot>> {
+    collection::user_collection_snapshot::find_between(db::get_db(), user.id, from, to).await
+}
+
+pub async fn find_state_history(user: &Model) -> anyhow::Result<Vec<UserStateLog>> {
+    collection::user_state_log::find_by_user(db::get_db(), user.id).await
+}
+
+pub async fn count_states_by_day(
+    from: DateTimeUtc,
+    to: DateTimeUtc,
+    offset_secs: i32,
+) -> anyhow::Result<Vec<StateDayCount>> {
+    collection::user_state_log::count_by_day(db::get_db(), from, to, offset_secs).await
+}
+
+pub async fn find_profile_history(
+    user: &Model,
+    field: Option<ProfileField>,
+) -> anyhow::Result<Vec<UserProfileHistory>> {
+    collection::user_profile_history::find_by_user(db::get_db(), user.id, field).await
+}
+
+/// Replace one collection list of a user in a single transaction, see
+/// [`collection::user_collection::replace_list`].
+pub async fn replace_collection_list(
+    user: &Model,
+    subject_type: SubjectType,
+    state: SubjectState,
+    items: Vec<CollectionItem>,
+    complete: bool,
+    at: DateTimeUtc,
+) -> anyhow::Result<()> {
+    let user_id = user.id;
+    db::get_db()
+        .transaction::<_, _, anyhow::Error>(|txn| {
+            Box::pin(async move {
+                collection::user_collection::replace_list(
+                    txn,
+                    user_id,
+                    subject_type,
+                    state,
+                    &items,
+                    complete,
+                    at,
+                )
+                .await
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn collection_list_synced_at(
+    user: &Model,
+    subject_type: SubjectType,
+    state: SubjectState,
+) -> anyhow::Result<Option<DateTimeUtc>> {
+    collection::user_collection::synced_at(db::get_db(), user.id, subject_type, state).await
+}
+
+pub async fn find_collection_list(
+    user: &Model,
+    subject_type: SubjectType,
+    state: Option<SubjectState>,
+) -> anyhow::Result<Vec<UserCollection>> {
+    collection::user_collection::find_list(db::get_db(), user.id, subject_type, state).await
+}
+
+pub async fn update_friends(
+    id: Uuid,
+    friends: Vec<Friend>,
+    at: DateTimeUtc,
+) -> anyhow::Result<Model> {
+    let user = db::get_db()
+        .transaction::<_, _, anyhow::Error>(|txn| {
+            Box::pin(async move { collection::user::update_friends(txn, id, &friends, at).await })
+        })
+        .await?;
+    Ok(user)
+}
+
+pub async fn find_friends_expired(cutoff: DateTimeUtc, limit: u64) -> anyhow::Result<Vec<Model>> {
+    collection::user::find_friends_expired(db::get_db(), cutoff, limit).await
+}
+
+pub async fn find_friends(user: &Model) -> anyhow::Result<Vec<UserFriend>> {
+    collection::user_friend::find_by_user(db::get_db(), user.id).await
+}
+
+pub async fn find_mutual_friends(user: &Model) -> anyhow::Result<Vec<String>> {
+    collection::user_friend::find_mutual(db::get_db(), user.id).await
+}
+
+pub async fn find_friend_suggestions(
+    user: &Model,
+    limit: u64,
+) -> anyhow::Result<Vec<FriendSuggestion>> {
+    collection::user_friend::find_suggestions(db::get_db(), user.id, limit).await
+}
+
+pub async fn find_undiscovered(limit: u64) -> anyhow::Result<Vec<Uid>> {
+    collection::user_friend::find_undiscovered(db::get_db(), limit).await
 }
@@ -1,2 +1,3 @@
 pub mod onair;
+pub mod text;
 pub mod user;
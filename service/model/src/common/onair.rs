@@ -1,37 +1,282 @@
 pub mod bangumi_data;
+pub mod broadcast;
+pub mod change;
 
-pub use bangumi_data::{BangumiData, BangumiItem, SiteList as Site};
+pub use bangumi_data::{BangumiData, BangumiItem, SiteList as Site, SiteMeta};
+pub use broadcast::{Broadcast, Period};
+pub use change::{ChangeKind, Diff};
+use chrono::{DateTime, Utc};
 use std::collections::{HashMap, HashSet};
 
 pub use crate::entity::on_air::SubjectId;
 pub type BangumiItemMap = HashMap<SubjectId, BangumiItem>;
+pub type SiteMetaMap = HashMap<Site, SiteMeta>;
 
 impl From<BangumiData> for BangumiItemMap {
     fn from(data: BangumiData) -> Self {
         data.items
-            .iter()
-            .filter_map(|item| {
-                for bangumi_data::Site { site, id, .. } in &item.sites {
-                    if !site.eq(&Site::Bangumi) {
-                        continue;
-                    }
-                    if let Some(id) = &id {
-                        if let Ok(id) = id.parse() {
-                            return Some((id, item.clone()));
-                        } else {
-                            tracing::warn!("Invalid Bangumi site id for item: {item:?}, id: {id}");
-                            return None;
-                        }
-                    } else {
-                        tracing::warn!("No Bangumi site id for item: {item:?}");
-                        return None;
-                    }
-                }
-                tracing::debug!("No Bangumi site for item: {:?}", item);
-                None
-            })
+            .into_iter()
+            .filter_map(|item| Some((item.subject_id()?, item)))
             .collect()
     }
 }
 
 pub type SubjectIds = HashSet<SubjectId>;
+
+/// Parse a bangumi-data timestamp, treating `""` as absent.
+pub fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    if time.is_empty() {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(time)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+}
+
+/// Parse a bangumi-data broadcast interval, treating `""` as absent.
+pub fn parse_broadcast(broadcast: &str) -> Option<Broadcast> {
+    if broadcast.is_empty() {
+        return None;
+    }
+    match broadcast.parse() {
+        Ok(broadcast) => Some(broadcast),
+        Err(e) => {
+            tracing::warn!("Invalid broadcast {broadcast:?}: {e}");
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Airing {
+    /// 1-based episode number counted from `begin`.
+    pub episode: u32,
+    pub time: DateTime<Utc>,
+}
+
+/// Progress of a broadcast at a given time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NextAir {
+    /// Episodes aired so far, estimated from elapsed repetitions since `begin`.
+    pub episode_index: u32,
+    /// `None` once the broadcast has ended.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_air: Option<DateTime<Utc>>,
+}
+
+/// Concrete airing slots of a broadcast, bounded by `begin`/`end` and,
+/// for sites, by the episode count of the main broadcast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub broadcast: Broadcast,
+    pub begin: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub episodes: Option<u32>,
+}
+
+impl Schedule {
+    fn first(&self) -> u32 {
+        self.begin.map_or(0, |b| self.broadcast.index_from(b))
+    }
+
+    /// Exclusive upper bound of occurrence indexes, `None` when unbounded.
+    fn last(&self) -> Option<u32> {
+        let first = self.first();
+        let by_end = self.end.map(|e| self.broadcast.index_after(e).max(first));
+        let by_count = self.episodes.map(|c| first.saturating_add(c));
+        [by_end, by_count, self.broadcast.limit()]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|last| last.max(first))
+    }
+
+    fn airing(&self, first: u32, n: u32) -> Option<Airing> {
+        if self.last().is_some_and(|last| n >= last) {
+            return None;
+        }
+        self.broadcast.nth(n).map(|time| Airing {
+            episode: n - first + 1,
+            time,
+        })
+    }
+
+    pub fn first_airing(&self) -> Option<Airing> {
+        let first = self.first();
+        self.airing(first, first)
+    }
+
+    /// Number of episodes, `None` when the broadcast has no known end.
+    pub fn count(&self) -> Option<u32> {
+        self.last().map(|last| last - self.first())
+    }
+
+    /// Number of episodes aired at or before `at`.
+    pub fn elapsed(&self, at: DateTime<Utc>) -> u32 {
+        let n = self.broadcast.index_after(at);
+        let n = self.last().map_or(n, |last| n.min(last));
+        n.saturating_sub(self.first())
+    }
+
+    /// First airing strictly after `at`.
+    pub fn next_after(&self, at: DateTime<Utc>) -> Option<Airing> {
+        let first = self.first();
+        let n = self.broadcast.index_after(at).max(first);
+        self.airing(first, n)
+    }
+
+    pub fn next_air(&self, at: DateTime<Utc>) -> NextAir {
+        NextAir {
+            episode_index: self.elapsed(at),
+            next_air: self.next_after(at).map(|airing| airing.time),
+        }
+    }
+
+    /// Airings in `[from, to)`.
+    pub fn between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> impl Iterator<Item = Airing> + '_ {
+        let first = self.first();
+        let start = self.broadcast.index_from(from).max(first);
+        (start..)
+            .map_while(move |n| self.airing(first, n))
+            .take_while(move |airing| airing.time < to)
+    }
+}
+
+impl BangumiItem {
+    /// Subject id from the `bangumi` site, items without one are skipped.
+    pub fn subject_id(&self) -> Option<SubjectId> {
+        for bangumi_data::Site { site, id, .. } in &self.sites {
+            if !site.eq(&Site::Bangumi) {
+                continue;
+            }
+            if let Some(id) = &id {
+                if let Ok(id) = id.parse() {
+                    return Some(id);
+                } else {
+                    tracing::warn!("Invalid Bangumi site id for item: {self:?}, id: {id}");
+                    return None;
+                }
+            } else {
+                tracing::warn!("No Bangumi site id for item: {self:?}");
+                return None;
+            }
+        }
+        tracing::debug!("No Bangumi site for item: {:?}", self);
+        None
+    }
+
+    /// Title translated to `lang` (a `titleTranslate` key such as `zh-Hans`),
+    /// falling back to the original title.
+    pub fn localized_title(&self, lang: Option<&str>) -> &str {
+        lang.and_then(|lang| self.title_translate.get(lang))
+            .and_then(|titles| titles.first())
+            .unwrap_or(&self.title)
+    }
+
+    pub fn begin_time(&self) -> Option<DateTime<Utc>> {
+        parse_time(&self.begin)
+    }
+
+    pub fn end_time(&self) -> Option<DateTime<Utc>> {
+        parse_time(&self.end)
+    }
+
+    /// Schedule of the main broadcast.
+    pub fn schedule(&self) -> Option<Schedule> {
+        Some(Schedule {
+            broadcast: parse_broadcast(&self.broadcast)?,
+            begin: self.begin_time(),
+            end: self.end_time(),
+            episodes: None,
+        })
+    }
+
+    /// Schedule of a site broadcast. Sites carry no `end`, so they are
+    /// limited to as many episodes as the main broadcast.
+    pub fn site_schedule(&self, site: &bangumi_data::Site) -> Option<Schedule> {
+        let broadcast = parse_broadcast(site.broadcast.as_deref().unwrap_or_default())?;
+        Some(Schedule {
+            broadcast,
+            begin: site.begin.as_deref().and_then(parse_time),
+            end: None,
+            episodes: self.schedule().and_then(|s| s.count()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        parse_time(s).unwrap()
+    }
+
+    fn item() -> BangumiItem {
+        serde_json::from_value(serde_json::json!({
+            "title": "瑠璃の宝石",
+            "titleTranslate": { "en": ["Ruri Rocks"] },
+            "type": "tv",
+            "lang": "ja",
+            "officialSite": "https://rurinohouseki.com/",
+            "begin": "2025-07-06T12:00:00.000Z",
+            "broadcast": "R/2025-07-06T12:00:00.000Z/P7D",
+            "end": "2025-09-28T12:30:00.000Z",
+            "sites": [
+                { "site": "bilibili", "id": "26624922", "begin": "2025-07-31T10:00:00.000Z", "broadcast": "R/2025-07-31T10:00:00.000Z/P7D" },
+                { "site": "nicovideo", "id": "rurinohouseki", "begin": "", "broadcast": "" }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_next_air() {
+        let item = item();
+        let schedule = item.schedule().unwrap();
+        assert_eq!(Some(13), schedule.count());
+
+        let next = schedule.next_air(utc("2025-07-01T00:00:00.000Z"));
+        assert_eq!(0, next.episode_index);
+        assert_eq!(Some(utc("2025-07-06T12:00:00.000Z")), next.next_air);
+
+        let next = schedule.next_air(utc("2025-07-06T12:00:00.000Z"));
+        assert_eq!(1, next.episode_index);
+        assert_eq!(Some(utc("2025-07-13T12:00:00.000Z")), next.next_air);
+
+        let next = schedule.next_air(utc("2025-10-01T00:00:00.000Z"));
+        assert_eq!(13, next.episode_index);
+        assert_eq!(None, next.next_air);
+    }
+
+    #[test]
+    fn test_site_next_air() {
+        let item = item();
+        let bilibili = item.site_schedule(&item.sites[0]).unwrap();
+        assert_eq!(Some(13), bilibili.count());
+        let next = bilibili.next_air(utc("2025-10-01T00:00:00.000Z"));
+        assert_eq!(9, next.episode_index);
+        assert_eq!(Some(utc("2025-10-02T10:00:00.000Z")), next.next_air);
+        assert!(item.site_schedule(&item.sites[1]).is_none());
+    }
+
+    #[test]
+    fn test_between() {
+        let item = item();
+        let schedule = item.schedule().unwrap();
+        let airings = schedule
+            .between(
+                utc("2025-09-20T00:00:00.000Z"),
+                utc("2025-10-20T00:00:00.000Z"),
+            )
+            .collect::<Vec<_>>();
+        assert_eq!(2, airings.len());
+        assert_eq!(12, airings[0].episode);
+        assert_eq!(13, airings[1].episode);
+    }
+}
@@ -1,4 +1,4 @@
-use sea_orm::FromJsonQueryResult;
+use sea_orm::{DeriveValueType, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, str::FromStr};
 
@@ -20,7 +20,17 @@ pub struct Site {
     pub comment: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+impl Site {
+    /// Fill `url` from the site's URL template when only `id` is known.
+    pub fn expand(&mut self, meta: &SiteMeta) {
+        if self.url.is_none() {
+            self.url = self.id.as_deref().map(|id| meta.render_url(id));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveValueType)]
+#[sea_orm(value_type = "String")]
 #[serde(rename_all = "lowercase")]
 pub enum ItemType {
     Tv,
@@ -37,7 +47,8 @@ pub enum SiteType {
     Resource,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveValueType)]
+#[sea_orm(value_type = "String")]
 pub enum Language {
     #[serde(rename = "ja")]
     Ja,
@@ -87,6 +98,12 @@ pub struct SiteMeta {
     pub site_type: Option<SiteType>,
 }
 
+impl SiteMeta {
+    pub fn render_url(&self, id: &str) -> String {
+        self.url_template.replace("{{id}}", id)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum SiteList {
@@ -146,3 +163,56 @@ impl TryFrom<String> for BangumiData {
         Self::from_str(&s)
     }
 }
+
+impl FromStr for SiteList {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        from_serde_str(s)
+    }
+}
+
+/// Write a value that serializes as a plain string, e.g. a unit enum variant.
+fn fmt_serde_str<T: Serialize>(value: &T, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => f.write_str(&s),
+        _ => Err(std::fmt::Error),
+    }
+}
+
+/// Inverse of `fmt_serde_str`.
+fn from_serde_str<T: serde::de::DeserializeOwned>(s: &str) -> anyhow::Result<T> {
+    let value = serde_json::from_value(serde_json::Value::String(s.to_string()))?;
+    Ok(value)
+}
+
+impl std::fmt::Display for SiteList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_serde_str(self, f)
+    }
+}
+
+impl std::fmt::Display for ItemType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_serde_str(self, f)
+    }
+}
+
+impl FromStr for ItemType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        from_serde_str(s)
+    }
+}
+
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_serde_str(self, f)
+    }
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        from_serde_str(s)
+    }
+}
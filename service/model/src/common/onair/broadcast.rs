@@ -0,0 +1,240 @@
+use chrono::{DateTime, Duration, Months, Utc};
+use std::str::FromStr;
+
+/// ISO 8601 duration as used by bangumi-data, e.g. `P7D`, `P1M`, `PT12H`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Period {
+    pub years: u32,
+    pub months: u32,
+    pub weeks: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+}
+
+impl Period {
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Calendar part of the period, applied with month arithmetic.
+    pub fn calendar_months(&self) -> u32 {
+        self.years * 12 + self.months
+    }
+
+    /// Fixed part of the period (weeks and below).
+    pub fn fixed(&self) -> Duration {
+        Duration::weeks(self.weeks as i64)
+            + Duration::days(self.days as i64)
+            + Duration::hours(self.hours as i64)
+            + Duration::minutes(self.minutes as i64)
+            + Duration::seconds(self.seconds as i64)
+    }
+
+    /// `start` shifted forward by `n` periods.
+    pub fn shift(&self, start: DateTime<Utc>, n: u32) -> Option<DateTime<Utc>> {
+        let months = self.calendar_months().checked_mul(n)?;
+        let time = start.checked_add_months(Months::new(months))?;
+        let fixed = self.fixed().checked_mul(i32::try_from(n).ok()?)?;
+        time.checked_add_signed(fixed)
+    }
+}
+
+impl FromStr for Period {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let rest = s
+            .strip_prefix('P')
+            .ok_or_else(|| anyhow::anyhow!("Invalid duration: {s}"))?;
+        let (date, time) = match rest.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (rest, None),
+        };
+        let mut period = Self::default();
+        let mut parse = |part: &str, in_time: bool| -> anyhow::Result<()> {
+            let mut digits = String::new();
+            for c in part.chars() {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    continue;
+                }
+                let value = digits
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid duration: {s}"))?;
+                digits.clear();
+                match (in_time, c) {
+                    (false, 'Y') => period.years = value,
+                    (false, 'M') => period.months = value,
+                    (false, 'W') => period.weeks = value,
+                    (false, 'D') => period.days = value,
+                    (true, 'H') => period.hours = value,
+                    (true, 'M') => period.minutes = value,
+                    (true, 'S') => period.seconds = value,
+                    _ => return Err(anyhow::anyhow!("Invalid duration: {s}")),
+                }
+            }
+            if !digits.is_empty() {
+                return Err(anyhow::anyhow!("Invalid duration: {s}"));
+            }
+            Ok(())
+        };
+        parse(date, false)?;
+        if let Some(time) = time {
+            parse(time, true)?;
+        }
+        Ok(period)
+    }
+}
+
+/// ISO 8601 repeating interval, e.g. `R/2025-07-06T12:00:00.000Z/P7D`.
+///
+/// `repeat` is the total number of occurrences, `None` when unbounded.
+/// An interval without the `R` prefix or with a zero period occurs once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Broadcast {
+    pub start: DateTime<Utc>,
+    pub period: Period,
+    pub repeat: Option<u32>,
+}
+
+impl Broadcast {
+    /// Total number of occurrences, `None` when unbounded.
+    pub fn limit(&self) -> Option<u32> {
+        if self.period.is_zero() {
+            return Some(self.repeat.map_or(1, |r| r.min(1)));
+        }
+        self.repeat
+    }
+
+    /// Time of the `n`-th occurrence, counting from zero.
+    pub fn nth(&self, n: u32) -> Option<DateTime<Utc>> {
+        if self.limit().is_some_and(|limit| n >= limit) {
+            return None;
+        }
+        self.period.shift(self.start, n)
+    }
+
+    pub fn occurrences(&self) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        (0..).map_while(|n| self.nth(n))
+    }
+
+    /// Index of the first occurrence at or after `time` (strictly after when
+    /// `strict`). Returns the number of occurrences when there is none.
+    fn position(&self, time: DateTime<Utc>, strict: bool) -> u32 {
+        let reached = |t: DateTime<Utc>| if strict { t > time } else { t >= time };
+        let mut n = 0;
+        let fixed = self.period.fixed();
+        if self.period.calendar_months() == 0 && fixed > Duration::zero() && time > self.start {
+            // jump right before the answer instead of walking every occurrence
+            let skip = (time - self.start).num_seconds() / fixed.num_seconds().max(1);
+            n = u32::try_from(skip.saturating_sub(1)).unwrap_or(u32::MAX);
+            if let Some(limit) = self.limit() {
+                n = n.min(limit);
+            }
+        }
+        while let Some(t) = self.nth(n) {
+            if reached(t) {
+                return n;
+            }
+            n += 1;
+        }
+        n
+    }
+
+    /// Index of the first occurrence at or after `time`.
+    pub fn index_from(&self, time: DateTime<Utc>) -> u32 {
+        self.position(time, false)
+    }
+
+    /// Index of the first occurrence strictly after `time`.
+    pub fn index_after(&self, time: DateTime<Utc>) -> u32 {
+        self.position(time, true)
+    }
+}
+
+impl FromStr for Broadcast {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parts = s.split('/').collect::<Vec<_>>();
+        let (repeat, start, period) = match parts.as_slice() {
+            [repeat, start, period] => {
+                let repeat = repeat
+                    .strip_prefix('R')
+                    .ok_or_else(|| anyhow::anyhow!("Invalid repeating interval: {s}"))?;
+                let repeat = if repeat.is_empty() {
+                    None
+                } else {
+                    Some(repeat.parse()?)
+                };
+                (repeat, start, period)
+            }
+            [start, period] => (Some(1), start, period),
+            _ => return Err(anyhow::anyhow!("Invalid repeating interval: {s}")),
+        };
+        let start = DateTime::parse_from_rfc3339(start)?.with_timezone(&Utc);
+        let period = period.parse()?;
+        Ok(Self {
+            start,
+            period,
+            repeat,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_period() {
+        let period: Period = "P7D".parse().unwrap();
+        assert_eq!(Duration::days(7), period.fixed());
+        let period: Period = "P1Y2M3W4DT5H6M7S".parse().unwrap();
+        assert_eq!(14, period.calendar_months());
+        assert_eq!(
+            Duration::days(25) + Duration::hours(5) + Duration::minutes(6) + Duration::seconds(7),
+            period.fixed()
+        );
+        assert!("P0D".parse::<Period>().unwrap().is_zero());
+        assert!("7D".parse::<Period>().is_err());
+        assert!("P7".parse::<Period>().is_err());
+        assert!("PT1D".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn test_parse_broadcast() {
+        let b: Broadcast = "R/2025-07-06T12:00:00.000Z/P7D".parse().unwrap();
+        assert_eq!(utc("2025-07-06T12:00:00Z"), b.start);
+        assert_eq!(None, b.repeat);
+        let b: Broadcast = "R3/2025-07-06T12:00:00.000Z/P1M".parse().unwrap();
+        assert_eq!(Some(3), b.repeat);
+        assert_eq!(3, b.occurrences().count());
+        assert!("".parse::<Broadcast>().is_err());
+        assert!(
+            "X/2025-07-06T12:00:00.000Z/P7D"
+                .parse::<Broadcast>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let b: Broadcast = "R/2025-07-06T12:00:00.000Z/P7D".parse().unwrap();
+        assert_eq!(Some(utc("2025-07-13T12:00:00Z")), b.nth(1));
+        assert_eq!(0, b.index_from(utc("2025-07-01T00:00:00Z")));
+        assert_eq!(0, b.index_from(utc("2025-07-06T12:00:00Z")));
+        assert_eq!(1, b.index_after(utc("2025-07-06T12:00:00Z")));
+        assert_eq!(13, b.index_after(utc("2025-09-28T12:30:00Z")));
+
+        let once: Broadcast = "R/2025-07-06T12:00:00.000Z/P0D".parse().unwrap();
+        assert_eq!(1, once.occurrences().count());
+        assert_eq!(1, once.index_after(utc("2026-01-01T00:00:00Z")));
+    }
+}
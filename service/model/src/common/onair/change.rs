@@ -0,0 +1,208 @@
+use super::bangumi_data::{BangumiItem, Site, SiteList};
+use sea_orm::{DeriveValueType, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, DeriveValueType)]
+#[sea_orm(value_type = "String")]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    /// First seen in bangumi-data.
+    Added,
+    /// Dropped from bangumi-data.
+    Removed,
+    /// Back in bangumi-data after being removed.
+    Restored,
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Restored => "restored",
+            ChangeKind::Changed => "changed",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ChangeKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "added" => Ok(ChangeKind::Added),
+            "removed" => Ok(ChangeKind::Removed),
+            "restored" => Ok(ChangeKind::Restored),
+            "changed" => Ok(ChangeKind::Changed),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A changed JSON field, `null` on the side where it is absent.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SiteChange {
+    pub site: SiteList,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Difference between two versions of an item. Sites are matched by
+/// `site` and `id`, so a new id shows up as one removed and one added site.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, FromJsonQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct Diff {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites_added: Vec<Site>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites_removed: Vec<Site>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites_changed: Vec<SiteChange>,
+}
+
+fn field_changes<T: Serialize>(old: &T, new: &T, skip: &[&str]) -> Vec<FieldChange> {
+    let old = serde_json::to_value(old).unwrap_or_default();
+    let new = serde_json::to_value(new).unwrap_or_default();
+    let (Some(old), Some(new)) = (old.as_object(), new.as_object()) else {
+        return Vec::new();
+    };
+    let fields = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    fields
+        .into_iter()
+        .filter(|field| !skip.contains(&field.as_str()))
+        .filter_map(|field| {
+            let (o, n) = (old.get(field), new.get(field));
+            (o != n).then(|| FieldChange {
+                field: field.clone(),
+                old: o.cloned().unwrap_or_default(),
+                new: n.cloned().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+impl Diff {
+    /// A newly seen item has all its sites added.
+    pub fn added(new: &BangumiItem) -> Self {
+        Self {
+            sites_added: new.sites.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn between(old: &BangumiItem, new: &BangumiItem) -> Self {
+        let mut diff = Self {
+            fields: field_changes(old, new, &["sites"]),
+            ..Default::default()
+        };
+        let mut matched = vec![false; old.sites.len()];
+        for site in &new.sites {
+            let found = (0..old.sites.len()).find(|&i| {
+                !matched[i] && old.sites[i].site == site.site && old.sites[i].id == site.id
+            });
+            let Some(i) = found else {
+                diff.sites_added.push(site.clone());
+                continue;
+            };
+            matched[i] = true;
+            let fields = field_changes(&old.sites[i], site, &[]);
+            if !fields.is_empty() {
+                diff.sites_changed.push(SiteChange {
+                    site: site.site.clone(),
+                    id: site.id.clone(),
+                    fields,
+                });
+            }
+        }
+        diff.sites_removed = old
+            .sites
+            .iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(site, _)| site.clone())
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+            && self.sites_added.is_empty()
+            && self.sites_removed.is_empty()
+            && self.sites_changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(broadcast: &str, sites: Value) -> BangumiItem {
+        serde_json::from_value(json!({
+            "title": "ぐらんぶる Season 2",
+            "titleTranslate": {},
+            "type": "tv",
+            "lang": "ja",
+            "officialSite": "https://grandblue-anime.com/",
+            "begin": "2025-07-07T15:30:00.000Z",
+            "broadcast": broadcast,
+            "end": "",
+            "sites": sites,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = item(
+            "R/2025-07-07T15:30:00.000Z/P7D",
+            json!([
+                { "site": "bangumi", "id": "515880" },
+                { "site": "abema", "id": "11-72", "begin": "" },
+                { "site": "prime", "id": "B0DBMTXW3G" },
+            ]),
+        );
+        assert!(Diff::between(&old, &old).is_empty());
+        assert_eq!(
+            vec![SiteList::Bangumi, SiteList::Abema, SiteList::Prime],
+            site_names(&Diff::added(&old).sites_added)
+        );
+
+        let new = item(
+            "R/2025-07-07T16:00:00.000Z/P7D",
+            json!([
+                { "site": "bangumi", "id": "515880" },
+                { "site": "abema", "id": "11-72", "begin": "2025-07-07T16:00:00.000Z" },
+                { "site": "bilibili", "id": "26714035" },
+            ]),
+        );
+        let diff = Diff::between(&old, &new);
+        assert_eq!(1, diff.fields.len());
+        assert_eq!("broadcast", diff.fields[0].field);
+        assert_eq!(json!("R/2025-07-07T16:00:00.000Z/P7D"), diff.fields[0].new);
+        assert_eq!(vec![SiteList::Bilibili], site_names(&diff.sites_added));
+        assert_eq!(vec![SiteList::Prime], site_names(&diff.sites_removed));
+        assert_eq!(1, diff.sites_changed.len());
+        assert_eq!("begin", diff.sites_changed[0].fields[0].field);
+    }
+
+    fn site_names(sites: &[Site]) -> Vec<SiteList> {
+        sites.iter().map(|s| s.site.clone()).collect()
+    }
+}
@@ -0,0 +1,46 @@
+/// Normalize a title or name for matching: trimmed, lowercased, with
+/// whitespace runs collapsed to a single space.
+///
+/// Migrations backfilling normalized columns use the SQL equivalent
+/// `lower(regexp_replace(btrim(x), '\s+', ' ', 'g'))`.
+pub fn normalize(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// How a search query matched, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Exact,
+    /// Equal after `normalize`.
+    Normalized,
+    Prefix,
+    Contains,
+    Fuzzy,
+}
+
+/// Escape `\`, `%` and `_` for use in a `LIKE ... ESCAPE '\'` pattern.
+pub fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!("spy x family", normalize("  SPY\tx  Family "));
+        assert_eq!("", normalize(" \n "));
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!("100\\% \\_a\\\\b", escape_like("100% _a\\b"));
+    }
+}
@@ -1,11 +1,9 @@
 use sea_orm::prelude::DateTimeUtc;
 use sea_orm::{DeriveValueType, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::{self, HashSet},
-    str::FromStr,
-};
+use std::str::FromStr;
 
+pub use crate::entity::on_air::SubjectId;
 pub use crate::entity::user::{Nid, Sid};
 
 #[derive(Deserialize, Debug, Clone, Serialize, PartialEq, Eq, Hash)]
@@ -41,7 +39,69 @@ impl ToString for Uid {
     }
 }
 
-pub type Names = HashSet<String>;
+/// A name, the day it was taken and the last day it was in use. Names
+/// recorded before the times were tracked have no `first_seen`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NameRecord {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<DateTimeUtc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<DateTimeUtc>,
+}
+
+impl NameRecord {
+    pub fn new(
+        name: String,
+        first_seen: Option<DateTimeUtc>,
+        last_seen: Option<DateTimeUtc>,
+    ) -> Self {
+        Self {
+            name,
+            first_seen,
+            last_seen,
+        }
+    }
+}
+
+/// A rename on the timeline: the new name and the day it was taken on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub name: String,
+    pub day: Option<DateTimeUtc>,
+}
+
+/// Names from `renames` given newest first. A name was in use until the
+/// next newer rename; the newest one is still in use and has no
+/// `last_seen` until the history is stored.
+pub fn names_from_renames(renames: impl IntoIterator<Item = Rename>) -> Names {
+    let mut names = Names::new();
+    let mut until = None;
+    for rename in renames {
+        let record = NameRecord::new(rename.name, rename.day, until);
+        until = rename.day;
+        merge_names(&mut names, [record]);
+    }
+    names
+}
+
+/// Names in chronological order of first use, names without times first.
+pub type Names = Vec<NameRecord>;
+
+/// Merge `records` into `names`, widening the seen range of names already
+/// present, and keep `names` chronological. A missing `first_seen` means
+/// the name predates the tracked times and is kept as such.
+pub fn merge_names(names: &mut Names, records: impl IntoIterator<Item = NameRecord>) {
+    for record in records {
+        let Some(known) = names.iter_mut().find(|n| n.name == record.name) else {
+            names.push(record);
+            continue;
+        };
+        known.first_seen = known.first_seen.min(record.first_seen);
+        known.last_seen = known.last_seen.max(record.last_seen);
+    }
+    names.sort_by_key(|n| (n.first_seen, n.last_seen));
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NameHistory {
@@ -50,7 +110,8 @@ pub struct NameHistory {
     pub names: Names,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveValueType)]
+#[sea_orm(value_type = "String")]
 #[serde(rename_all = "lowercase")]
 pub enum SubjectState {
     Do,      // 在X
@@ -61,12 +122,22 @@ pub enum SubjectState {
     Dropped, // 抛弃
 }
 
+impl SubjectState {
+    pub const ALL: [SubjectState; 5] = [
+        SubjectState::Do,
+        SubjectState::Collect,
+        SubjectState::Wish,
+        SubjectState::OnHold,
+        SubjectState::Dropped,
+    ];
+}
+
 impl FromStr for SubjectState {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
-            "do" => Ok(SubjectState::Do),
+            "do" | "doing" => Ok(SubjectState::Do),
             "collect" => Ok(SubjectState::Collect),
             "wish" => Ok(SubjectState::Wish),
             "on_hold" => Ok(SubjectState::OnHold),
@@ -76,7 +147,21 @@ impl FromStr for SubjectState {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+impl std::fmt::Display for SubjectState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SubjectState::Do => "do",
+            SubjectState::Collect => "collect",
+            SubjectState::Wish => "wish",
+            SubjectState::OnHold => "on_hold",
+            SubjectState::Dropped => "dropped",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveValueType)]
+#[sea_orm(value_type = "String")]
 #[serde(rename_all = "lowercase")]
 pub enum SubjectType {
     Anime,
@@ -101,6 +186,59 @@ impl FromStr for SubjectType {
     }
 }
 
+impl std::fmt::Display for SubjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SubjectType::Anime => "anime",
+            SubjectType::Game => "game",
+            SubjectType::Book => "book",
+            SubjectType::Music => "music",
+            SubjectType::Real => "real",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Tags a user gave a subject.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct SubjectTags(pub Vec<String>);
+
+/// A subject on one of the collection lists of a user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionItem {
+    pub subject_id: SubjectId,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Out of 10.
+    pub rating: Option<i16>,
+    pub tags: SubjectTags,
+    pub comment: Option<String>,
+    pub marked_at: Option<DateTimeUtc>,
+}
+
+/// One page of a collection list.
+#[derive(Debug, Clone, Default)]
+pub struct CollectionPage {
+    pub items: Vec<CollectionItem>,
+    /// Whether a following page exists.
+    pub has_next: bool,
+}
+
+/// An entry of `/user/{uid}/friends`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Friend {
+    pub uid: Uid,
+    pub name: String,
+}
+
+/// When the friends of a user were last scraped and how many there were.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FriendList {
+    pub count: usize,
+    pub update_at: DateTimeUtc,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TypedCollection {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -145,7 +283,7 @@ impl TypedCollection {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, FromJsonQueryResult)]
 pub struct Collections {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub anime: Option<TypedCollection>,
@@ -224,12 +362,111 @@ impl FromStr for UserState {
     }
 }
 
+/// An entry of `ul.network_service` on the user page, e.g. a Twitter handle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkService {
+    pub service: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network_services: Vec<NetworkService>,
+}
+
+impl Profile {
+    /// Every tracked field as text, `None` when the field is empty. Network
+    /// services are written as JSON.
+    pub fn fields(&self) -> [(ProfileField, Option<String>); 4] {
+        let network_services = (!self.network_services.is_empty())
+            .then(|| serde_json::to_string(&self.network_services).ok())
+            .flatten();
+        [
+            (ProfileField::Avatar, self.avatar.clone()),
+            (ProfileField::Sign, self.sign.clone()),
+            (ProfileField::Bio, self.bio.clone()),
+            (ProfileField::NetworkServices, network_services),
+        ]
+    }
+}
+
+/// Profile field whose values are tracked over time.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, DeriveValueType)]
+#[sea_orm(value_type = "String")]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileField {
+    Avatar,
+    Sign,
+    Bio,
+    NetworkServices,
+}
+
+impl std::fmt::Display for ProfileField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ProfileField::Avatar => "avatar",
+            ProfileField::Sign => "sign",
+            ProfileField::Bio => "bio",
+            ProfileField::NetworkServices => "network_services",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FromStr for ProfileField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "avatar" => Ok(ProfileField::Avatar),
+            "sign" => Ok(ProfileField::Sign),
+            "bio" => Ok(ProfileField::Bio),
+            "network_services" => Ok(ProfileField::NetworkServices),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Column a user listing is ordered by.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSort {
+    #[default]
+    UpdateAt,
+    JoinTime,
+    LastActive,
+}
+
+impl UserSort {
+    /// Value of the sort column for `user`.
+    pub fn key(&self, user: &crate::entity::user::Model) -> Option<DateTimeUtc> {
+        match self {
+            UserSort::UpdateAt => Some(user.update_at),
+            UserSort::JoinTime => user.join_time,
+            UserSort::LastActive => user.last_active,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, FromJsonQueryResult)]
 pub struct Extra {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name_history: Option<NameHistory>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub collections: Option<Collections>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub friends: Option<FriendList>,
 }
 
 impl Default for Extra {
@@ -237,6 +474,8 @@ impl Default for Extra {
         Self {
             name_history: None,
             collections: None,
+            profile: None,
+            friends: None,
         }
     }
 }
@@ -249,6 +488,12 @@ impl Extra {
         if let Some(c) = other.collections {
             self.collections = Some(c);
         }
+        if let Some(p) = other.profile {
+            self.profile = Some(p);
+        }
+        if let Some(f) = other.friends {
+            self.friends = Some(f);
+        }
         self
     }
 
@@ -264,22 +509,35 @@ impl Extra {
         self
     }
 
+    pub fn update_profile(&mut self, profile: Profile) -> &mut Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn update_friends(&mut self, friends: FriendList) -> &mut Self {
+        self.friends = Some(friends);
+        self
+    }
+
     pub fn replace_name_history(&mut self, name_history: NameHistory) -> &mut Self {
         self.name_history = Some(name_history);
         self
     }
 
+    /// Merge `name_history` in. The newest dated name is the current one
+    /// and was last seen now.
     pub fn update_name_history(&mut self, name_history: NamesUpdate) -> &mut Self {
-        if let Some(nh) = &mut self.name_history {
-            nh.update_at = chrono::Utc::now();
-            nh.key_point = name_history.key_point;
-            nh.names.extend(name_history.names);
-        } else {
-            self.name_history = Some(NameHistory {
-                update_at: chrono::Utc::now(),
-                key_point: name_history.key_point,
-                names: name_history.names,
-            });
+        let now = chrono::Utc::now();
+        let nh = self.name_history.get_or_insert_with(|| NameHistory {
+            update_at: now,
+            key_point: name_history.key_point,
+            names: Names::new(),
+        });
+        nh.update_at = now;
+        nh.key_point = name_history.key_point;
+        merge_names(&mut nh.names, name_history.names);
+        if let Some(current) = nh.names.last_mut().filter(|n| n.first_seen.is_some()) {
+            current.last_seen = current.last_seen.max(Some(now));
         }
         self
     }
@@ -302,6 +560,9 @@ pub struct InitUser {
     pub state: UserState,
     pub collections: Option<Collections>,
     pub names_update: Option<NamesUpdate>,
+    pub profile: Option<Profile>,
+    /// Page the data was scraped from.
+    pub source: Option<String>,
 }
 
 impl Default for InitUser {
@@ -315,6 +576,8 @@ impl Default for InitUser {
             state: UserState::Abondon,
             collections: None,
             names_update: None,
+            profile: None,
+            source: None,
         }
     }
 }
@@ -415,6 +678,16 @@ impl InitUser {
         self.collections = collections;
         self
     }
+
+    pub fn set_profile(&mut self, profile: Option<Profile>) -> &mut Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn set_source(&mut self, source: Option<String>) -> &mut Self {
+        self.source = source;
+        self
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -423,11 +696,81 @@ pub struct NamesUpdate {
     pub names: Names,
 }
 
+/// Renames on one timeline page, newest first.
+#[derive(Debug, Clone)]
+pub struct TimelineRenames {
+    pub key_point: DateTimeUtc,
+    pub renames: Vec<Rename>,
+}
+
 impl From<Extra> for Names {
     fn from(extra: Extra) -> Self {
         let Some(nh) = extra.name_history else {
-            return collections::HashSet::new();
+            return Names::new();
         };
         nh.names
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> Option<DateTimeUtc> {
+        Some(format!("{s}T00:00:00Z").parse().unwrap())
+    }
+
+    fn rename(name: &str, s: &str) -> Rename {
+        Rename {
+            name: name.to_string(),
+            day: day(s),
+        }
+    }
+
+    #[test]
+    fn test_merge_names() {
+        let mut names = vec![
+            NameRecord::new("old".to_string(), None, None),
+            NameRecord::new("b".to_string(), day("2024-05-01"), day("2024-06-01")),
+        ];
+        merge_names(
+            &mut names,
+            [
+                NameRecord::new("c".to_string(), day("2025-01-01"), None),
+                NameRecord::new("b".to_string(), day("2023-01-01"), day("2024-01-01")),
+                NameRecord::new("a".to_string(), day("2024-01-01"), day("2024-05-01")),
+                NameRecord::new("old".to_string(), day("2025-02-01"), day("2025-03-01")),
+            ],
+        );
+        let order = names.iter().map(|n| n.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["old", "b", "a", "c"], order);
+        assert_eq!(None, names[0].first_seen);
+        assert_eq!(day("2025-03-01"), names[0].last_seen);
+        assert_eq!(day("2023-01-01"), names[1].first_seen);
+        assert_eq!(day("2024-06-01"), names[1].last_seen);
+    }
+
+    #[test]
+    fn test_names_from_renames() {
+        let names = names_from_renames([
+            rename("c", "2025-01-01"),
+            rename("b", "2024-05-01"),
+            rename("a", "2024-01-01"),
+            rename("b", "2023-01-01"),
+        ]);
+        let order = names.iter().map(|n| n.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["b", "a", "c"], order);
+        assert_eq!(day("2023-01-01"), names[0].first_seen);
+        assert_eq!(day("2025-01-01"), names[0].last_seen);
+        assert_eq!(day("2024-05-01"), names[1].last_seen);
+        assert_eq!(None, names[2].last_seen);
+
+        let mut extra = Extra::default();
+        extra.update_name_history(NamesUpdate {
+            key_point: day("2025-01-01").unwrap(),
+            names,
+        });
+        let names = extra.name_history.unwrap().names;
+        assert!(names[2].last_seen > day("2025-01-01"));
+    }
+}
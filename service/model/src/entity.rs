@@ -4,4 +4,14 @@ pub mod prelude;
 
 pub mod key_value;
 pub mod on_air;
+pub mod on_air_change;
+pub mod on_air_title;
 pub mod user;
+pub mod user_collection;
+pub mod user_collection_list;
+pub mod user_collection_snapshot;
+pub mod user_friend;
+pub mod user_name;
+pub mod user_profile_history;
+pub mod user_sid_alias;
+pub mod user_state_log;
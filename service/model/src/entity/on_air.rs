@@ -1,3 +1,4 @@
+use crate::common::onair::bangumi_data::{ItemType, Language};
 use sea_orm::entity::prelude::*;
 
 pub type SubjectId = i32;
@@ -8,6 +9,12 @@ pub struct Model {
     pub subject: SubjectId,
     #[sea_orm(column_type = "JsonBinary")]
     pub data: crate::common::onair::BangumiItem,
+    pub begin: Option<DateTimeUtc>,
+    pub end: Option<DateTimeUtc>,
+    pub item_type: ItemType,
+    pub lang: Language,
+    /// Set once the item disappears from bangumi-data, cleared if it returns.
+    pub removed_at: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,21 @@
+use super::on_air::SubjectId;
+use crate::common::onair::{ChangeKind, Diff};
+use sea_orm::entity::prelude::*;
+
+/// Per-subject change recorded by every on-air flush.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "on_air_change")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub subject: SubjectId,
+    pub kind: ChangeKind,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub diff: Diff,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,19 @@
+use super::on_air::SubjectId;
+use sea_orm::entity::prelude::*;
+
+/// Search index over original and translated titles, rebuilt on every flush.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "on_air_title")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub subject: SubjectId,
+    pub title: String,
+    pub normalized: String,
+    pub lang: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,4 +2,5 @@
 
 pub use super::key_value::Entity as KeyValue;
 pub use super::on_air::Entity as OnAir;
+pub use super::on_air_title::Entity as OnAirTitle;
 pub use super::user::Entity as User;
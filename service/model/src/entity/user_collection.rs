@@ -0,0 +1,30 @@
+use crate::common::user::{SubjectState, SubjectTags, SubjectType};
+use sea_orm::entity::prelude::*;
+
+use super::on_air::SubjectId;
+
+/// A subject on the collection lists of a user. A subject is on one list of
+/// its type at a time.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_collection")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    pub subject_type: SubjectType,
+    pub subject_id: SubjectId,
+    pub state: SubjectState,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub rating: Option<i16>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub tags: SubjectTags,
+    pub comment: Option<String>,
+    pub marked_at: Option<DateTimeUtc>,
+    pub update_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,19 @@
+use crate::common::user::{SubjectState, SubjectType};
+use sea_orm::entity::prelude::*;
+
+/// When a collection list of a user was last scraped in full.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_collection_list")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    pub subject_type: SubjectType,
+    pub state: SubjectState,
+    pub synced_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,19 @@
+use crate::common::user::Collections;
+use sea_orm::entity::prelude::*;
+
+/// Collection counts of a user, recorded whenever they change.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_collection_snapshot")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub collections: Collections,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use crate::entity::user::Nid;
+use sea_orm::entity::prelude::*;
+
+/// A friend listed on the friends page of a user. `friend_uid` is the uid
+/// the page links to, `friend_nid` is set when that uid is numeric.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_friend")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    pub friend_uid: String,
+    pub friend_nid: Option<Nid>,
+    pub name: String,
+    pub first_seen: DateTimeUtc,
+    pub last_seen: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,21 @@
+use crate::common::user::ProfileField;
+use sea_orm::entity::prelude::*;
+
+/// A value a profile field of a user had, and when it was first and last
+/// seen. An empty `value` means the field was cleared.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_profile_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    pub field: ProfileField,
+    pub value: Option<String>,
+    pub first_seen: DateTimeUtc,
+    pub last_seen: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,17 @@
+use super::user::Sid;
+use sea_orm::entity::prelude::*;
+
+/// A sid a user has been known by before renaming it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_sid_alias")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sid: Sid,
+    pub user_id: Uuid,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use crate::common::user::UserState;
+use sea_orm::entity::prelude::*;
+
+/// A change of the state of a user, `from_state` is empty when the user was
+/// first seen.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_state_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub user_id: Uuid,
+    pub from_state: Option<UserState>,
+    pub to_state: UserState,
+    pub observed_at: DateTimeUtc,
+    /// Page the new state was scraped from.
+    pub source: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
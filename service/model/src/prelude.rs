@@ -1,10 +1,19 @@
 pub use crate::entity::key_value::Model as KeyValue;
 pub use crate::entity::on_air::{Model as OnAir, SubjectId};
+pub use crate::entity::on_air_change::Model as OnAirChange;
 pub use crate::entity::user::{Model as User, Nid, Sid};
+pub use crate::entity::user_collection::Model as UserCollection;
+pub use crate::entity::user_collection_snapshot::Model as UserCollectionSnapshot;
+pub use crate::entity::user_friend::Model as UserFriend;
+pub use crate::entity::user_profile_history::Model as UserProfileHistory;
+pub use crate::entity::user_sid_alias::Model as UserSidAlias;
+pub use crate::entity::user_state_log::Model as UserStateLog;
 
-pub use crate::common::onair::{BangumiItem, BangumiItemMap};
+pub use crate::common::onair::{BangumiItem, BangumiItemMap, SiteMeta, SiteMetaMap};
 pub use crate::common::user::{
-    Collections, Extra, InitUser, NameHistory, Names, SubjectType, TypedCollection, Uid, UserState,
+    CollectionItem, CollectionPage, Collections, Extra, Friend, FriendList, InitUser, NameHistory,
+    NameRecord, Names, NetworkService, Profile, ProfileField, SubjectState, SubjectTags,
+    SubjectType, TypedCollection, Uid, UserSort, UserState,
 };
 
-pub use db::prelude::DateTimeUtc;
+pub use db::prelude::{DateTimeUtc, Uuid};